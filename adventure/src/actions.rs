@@ -0,0 +1,2755 @@
+use core::cmp::min;
+
+//
+// reviewed: 2025-04-21
+//           2026-08-21
+//
+use crate::export;
+use crate::lib::api::{
+    Hardware, Printer, SDCARD_SECTOR_SIZE_BYTES, u8_slice_bits_to_u32, u8_slice_to_u32,
+    u8_slice_to_u32_checked,
+};
+use crate::lib::arena::{Arena, Id};
+use crate::lib::cursor_buffer::{CursorBuffer, CursorBufferIterator};
+use crate::lib::cursor_buffer_history::CursorBufferHistory;
+use crate::lib::fat::{self, Cluster};
+use crate::lib::format::Number;
+use crate::model::{
+    DEFAULT_BULK, DEFAULT_CAPACITY, DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, DEFAULT_WEIGHT, Door,
+    DoorState, Entity, EntityId, Link, LinkName, LinkNameId, Location, LocationId, Message, Name,
+    Note, Object, ObjectId, World,
+};
+use crate::persistence;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+const COMMAND_BUFFER_SIZE: usize = 526;
+// note: enough to support write to SD card sector of 512 byte
+
+pub type CommandBuffer = CursorBuffer<COMMAND_BUFFER_SIZE, u8>;
+pub type CommandBufferIterator<'a> =
+    CursorBufferIterator<'a, COMMAND_BUFFER_SIZE, u8, fn(&u8) -> bool>;
+
+const COMMAND_HISTORY_COUNT: usize = 8;
+
+pub type CommandHistory = CursorBufferHistory<COMMAND_HISTORY_COUNT, COMMAND_BUFFER_SIZE, u8>;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub enum Error {
+    NotUnderstood,
+    GoWhere,
+    NoSuchExit,
+    CannotGoThere,
+    TakeWhat,
+    ObjectNotHere,
+    DropWhat,
+    ObjectNotInInventory,
+    GiveToWhom,
+    GiveWhat,
+    EntityNotHere,
+    WhatSector,
+    WhichLeds,
+    WhatObjectName,
+    ObjectAlreadyExists,
+    WhatToLinkName,
+    WhatBackLinkName,
+    WhatNewLocationName,
+    LocationAlreadyExists,
+    LinkFromLocationAlreadyExists,
+    WhatEntityName,
+    EntityAlreadyExists,
+    SayWhat,
+    TellToWhom,
+    TellWhat,
+    NoSuchCommand,
+    SaveFailed,
+    LoadFailed,
+    ListFailed,
+    CatWhat,
+    CatFailed,
+    ChangeDirectoryFailed,
+    WriteToWhat,
+    WriteFailed,
+    AppendToWhat,
+    AppendWhat,
+    AppendFailed,
+    RemoveWhat,
+    RemoveFailed,
+    MakeDirectoryWhat,
+    MakeDirectoryFailed,
+    OutOfMemory,
+    HeapCorrupt,
+    MemoryInfoWhat,
+    StaleId,
+    RemoveObjectWhat,
+    NoSuchObject,
+    RemoveLocationWhat,
+    NoSuchLocation,
+    CannotRemoveCurrentLocation,
+    RemoveEntityWhat,
+    NoSuchEntity,
+    CannotRemoveSelf,
+    UnlinkWhat,
+    RenameWhat,
+    WhatNewName,
+    DescribeWhat,
+    ExamineWhat,
+    NotHere,
+    LinkWhat,
+    DoorWhat,
+    NotADoor,
+    OpenWhat,
+    CloseWhat,
+    LockWhat,
+    UnlockWhat,
+    AlreadyOpen,
+    AlreadyClosed,
+    AlreadyLocked,
+    NotLocked,
+    DoorClosed,
+    DoorLocked,
+    DoorOpen,
+    NoLock,
+    NoKey,
+    TooHeavy,
+    HandsFull,
+    ContainerFull,
+    SetObjectWhat,
+    SetEntityWhat,
+    NotANumber,
+    GotoWhere,
+    ExportWhat,
+    AlreadyThere,
+    NoRoute,
+    TakeFromWhat,
+    ObjectNotInContainer,
+    PutWhat,
+    PutInWhat,
+    NotAContainer,
+    ContainerCycle,
+    ContainerWhat,
+}
+
+pub struct ActionContext<'a> {
+    pub printer: &'a mut dyn Printer,
+    pub hardware: &'a mut dyn Hardware,
+    pub world: &'a mut World,
+    pub entity: EntityId,
+    pub tokens: &'a mut CommandBufferIterator<'a>,
+    /// Current directory of the file system on the SD card.
+    pub working_directory: &'a mut Cluster,
+}
+
+pub fn look(ctx: &mut ActionContext) -> Result<()> {
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    let location = get(ctx.printer, &ctx.world.locations, entity.location)?;
+
+    ctx.printer.p(b"u r in ");
+    ctx.printer.p(&location.name);
+    ctx.printer.nl();
+
+    ctx.printer.p(b"u c ");
+    let mut count = 0;
+    for x in location
+        .entities
+        .iter()
+        .filter(|&&eid| eid != ctx.entity)
+        .filter_map(|&eid| ctx.world.entities.get(eid))
+    {
+        if count != 0 {
+            ctx.printer.p(b", ");
+        }
+        count += 1;
+        ctx.printer.p(&x.name);
+    }
+    for x in location
+        .objects
+        .iter()
+        .filter_map(|&oid| ctx.world.objects.get(oid))
+    {
+        if count != 0 {
+            ctx.printer.p(b", ");
+        }
+        count += 1;
+        print_object(ctx.printer, &ctx.world.objects, x);
+    }
+    if count == 0 {
+        ctx.printer.p(b"nothing");
+    }
+    ctx.printer.nl();
+
+    ctx.printer.p(b"exits: ");
+    count = 0;
+    for (x, door) in location
+        .links
+        .iter()
+        .filter_map(|x| Some((ctx.world.link_names.get(x.link_name)?, x.door)))
+    {
+        if count != 0 {
+            ctx.printer.p(b", ");
+        }
+        count += 1;
+        ctx.printer.p(&x.name);
+        match door.map(|x| x.state) {
+            Some(DoorState::Closed) => ctx.printer.p(b" (closed)"),
+            Some(DoorState::Locked) => ctx.printer.p(b" (locked)"),
+            Some(DoorState::Open) | None => {}
+        }
+    }
+    if count == 0 {
+        ctx.printer.p(b"none");
+    }
+    ctx.printer.nl();
+
+    if !location.note.is_empty() {
+        ctx.printer.pl(&location.note);
+    }
+
+    // descriptions of what is seen, if any
+    for x in location
+        .entities
+        .iter()
+        .filter(|&&eid| eid != ctx.entity)
+        .filter_map(|&eid| ctx.world.entities.get(eid))
+        .filter(|x| !x.description.is_empty())
+    {
+        print_description(ctx.printer, &x.name, &x.description);
+    }
+    for x in location
+        .objects
+        .iter()
+        .filter_map(|&oid| ctx.world.objects.get(oid))
+        .filter(|x| !x.description.is_empty())
+    {
+        print_description(ctx.printer, &x.name, &x.description);
+    }
+
+    for x in &entity.messages {
+        ctx.printer.pl(x);
+    }
+
+    // clear messages after displayed
+    get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?
+        .messages
+        .clear();
+
+    Ok(())
+}
+
+pub fn go(ctx: &mut ActionContext) -> Result<()> {
+    let Some(named_link) = ctx.tokens.next() else {
+        ctx.printer.p(b"go where");
+        ctx.printer.nlc(2);
+        return Err(Error::GoWhere);
+    };
+
+    go_named_link(ctx, named_link)
+}
+
+pub fn go_named_link(ctx: &mut ActionContext, link_name: &[u8]) -> Result<()> {
+    // find link id
+    let Some(link_name_id) = ctx.world.link_names.find(|x| x.name == link_name) else {
+        ctx.printer.p(b"cannot go there");
+        ctx.printer.nlc(2);
+        return Err(Error::NoSuchExit);
+    };
+
+    // move entity
+    let (from_location_id, to_location_id, link_index) = {
+        let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+        let from_location_id = entity.location;
+        let from_location = get(ctx.printer, &ctx.world.locations, from_location_id)?;
+
+        // find "to" location id
+        let Some((link_index, lnk)) = from_location
+            .links
+            .iter()
+            .enumerate()
+            .find(|(_, x)| x.link_name == link_name_id)
+        else {
+            ctx.printer.p(b"cannot go there");
+            ctx.printer.nlc(2);
+            return Err(Error::CannotGoThere);
+        };
+        match lnk.door.map(|x| x.state) {
+            Some(DoorState::Closed) => {
+                return Err(exit_error(
+                    ctx.printer,
+                    link_name,
+                    b" is closed",
+                    Error::DoorClosed,
+                ));
+            }
+            Some(DoorState::Locked) => {
+                return Err(exit_error(
+                    ctx.printer,
+                    link_name,
+                    b" is locked",
+                    Error::DoorLocked,
+                ));
+            }
+            Some(DoorState::Open) | None => {}
+        }
+        let to_location_id = lnk.location;
+
+        let to_location = get_mut(ctx.printer, &mut ctx.world.locations, to_location_id)?;
+        reserve(ctx.printer, &mut to_location.entities, 1)?;
+
+        // note: checked before the world is changed
+        let from_location = get_mut(ctx.printer, &mut ctx.world.locations, from_location_id)?;
+        let Some(pos) = from_location.entities.iter().position(|&x| x == ctx.entity) else {
+            return Err(stale_id(ctx.printer));
+        };
+
+        // remove entity from old location
+        from_location.entities.remove(pos);
+
+        // add entity to new location
+        get_mut(ctx.printer, &mut ctx.world.locations, to_location_id)?
+            .entities
+            .push(ctx.entity);
+
+        // update entity location
+        entity.location = to_location_id;
+
+        (from_location_id, to_location_id, link_index)
+    };
+
+    let entity_name = get(ctx.printer, &ctx.world.entities, ctx.entity)?.name;
+
+    // send message to entities in 'from_location' that entity has left
+    send_message_to_entities_in_location(
+        ctx.world,
+        from_location_id,
+        &[ctx.entity],
+        Message::from_parts(&[&entity_name, b" left to ", link_name]),
+    );
+
+    // find link name that leads from 'to_location_id' to 'from_location_id'
+    // note: a one-way link has no link back
+    let to_location = get(ctx.printer, &ctx.world.locations, to_location_id)?;
+    let back_link_name = find_back_link(ctx.world, from_location_id, link_index)
+        .and_then(|i| to_location.links.get(i))
+        .and_then(|x| ctx.world.link_names.get(x.link_name));
+
+    // send message to entities in 'to_location' that entity has arrived
+    let message = back_link_name.map_or_else(
+        || Message::from_parts(&[&entity_name, b" arrived"]),
+        |x| Message::from_parts(&[&entity_name, b" arrived from ", &x.name]),
+    );
+    send_message_to_entities_in_location(ctx.world, to_location_id, &[ctx.entity], message);
+
+    Ok(())
+}
+
+pub fn go_north(ctx: &mut ActionContext) -> Result<()> {
+    go_named_link(ctx, b"north")
+}
+
+pub fn go_east(ctx: &mut ActionContext) -> Result<()> {
+    go_named_link(ctx, b"east")
+}
+
+pub fn go_south(ctx: &mut ActionContext) -> Result<()> {
+    go_named_link(ctx, b"south")
+}
+
+pub fn go_west(ctx: &mut ActionContext) -> Result<()> {
+    go_named_link(ctx, b"west")
+}
+
+pub fn goto(ctx: &mut ActionContext) -> Result<()> {
+    let route = find_route_to(ctx)?;
+
+    // note: each step is a go thus a closed door or a missing exit stops the walk
+    for (i, (link_name_id, _)) in route.into_iter().enumerate() {
+        let link_name = get(ctx.printer, &ctx.world.link_names, link_name_id)?.name;
+        if let Err(e) = go_named_link(ctx, &link_name) {
+            if i == 0 {
+                return Err(e);
+            }
+            // note: the entity has moved thus the walk counts as done
+            let location_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+            ctx.printer.p(b"u stopped in ");
+            ctx.printer
+                .pl(&get(ctx.printer, &ctx.world.locations, location_id)?.name);
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn route(ctx: &mut ActionContext) -> Result<()> {
+    let route = find_route_to(ctx)?;
+
+    // note: the route ignores doors thus they are shown as with look
+    let mut from_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    for (i, &(link_name_id, location_id)) in route.iter().enumerate() {
+        if i != 0 {
+            ctx.printer.p(b", ");
+        }
+        ctx.printer
+            .p(&get(ctx.printer, &ctx.world.link_names, link_name_id)?.name);
+        ctx.printer.p(b" to ");
+        ctx.printer
+            .p(&get(ctx.printer, &ctx.world.locations, location_id)?.name);
+        let door = get(ctx.printer, &ctx.world.locations, from_id)?
+            .links
+            .iter()
+            .find(|x| x.link_name == link_name_id && x.location == location_id)
+            .and_then(|x| x.door);
+        match door.map(|x| x.state) {
+            Some(DoorState::Closed) => ctx.printer.p(b" (closed)"),
+            Some(DoorState::Locked) => ctx.printer.p(b" (locked)"),
+            Some(DoorState::Open) | None => {}
+        }
+        from_id = location_id;
+    }
+    ctx.printer.nl();
+
+    Ok(())
+}
+
+pub fn map(ctx: &mut ActionContext) -> Result<()> {
+    let here_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    let grid = layout_map(ctx.world, here_id);
+
+    // bounds of the laid out locations
+    let mut min_x = MAP_SIZE;
+    let mut max_x = 0;
+    let mut min_y = MAP_SIZE;
+    let mut max_y = 0;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, _) in row.iter().enumerate().filter(|(_, cell)| cell.is_some()) {
+            min_x = min(min_x, x);
+            max_x = max_x.max(x);
+            min_y = min(min_y, y);
+            max_y = max_y.max(y);
+        }
+    }
+
+    for y in min_y..=max_y {
+        let mut line = [b' '; MAP_WIDTH];
+        for x in min_x..=max_x {
+            let column = (x - min_x) * (MAP_CELL_WIDTH + 1);
+            let Some(location) = grid[y][x].and_then(|id| ctx.world.locations.get(id)) else {
+                continue;
+            };
+            let name = &location.name[..min(location.name.len(), MAP_CELL_WIDTH - 2)];
+            let end = column + name.len() + 2;
+            let (open, close) = if grid[y][x] == Some(here_id) {
+                (b'<', b'>')
+            } else {
+                (b'[', b']')
+            };
+            line[column] = open;
+            line[column + 1..end - 1].copy_from_slice(name);
+            line[end - 1] = close;
+            if x < max_x && map_connected(ctx.world, &grid, x, y, 1, 0) {
+                line[end..=column + MAP_CELL_WIDTH].fill(b'-');
+            }
+        }
+        print_map_line(ctx.printer, &line);
+
+        if y == max_y {
+            break;
+        }
+        let mut line = [b' '; MAP_WIDTH];
+        for x in min_x..=max_x {
+            let column = (x - min_x) * (MAP_CELL_WIDTH + 1);
+            if map_connected(ctx.world, &grid, x, y, 0, 1) {
+                line[column + 1] = b'|';
+            }
+            if x < max_x {
+                let south_east = map_connected(ctx.world, &grid, x, y, 1, 1);
+                let south_west = map_connected(ctx.world, &grid, x + 1, y, -1, 1);
+                line[column + MAP_CELL_WIDTH] = match (south_east, south_west) {
+                    (true, true) => b'X',
+                    (true, false) => b'\\',
+                    (false, true) => b'/',
+                    (false, false) => b' ',
+                };
+            }
+        }
+        print_map_line(ctx.printer, &line);
+    }
+
+    // links that are not drawn such as up and down or that do not fit the geometry
+    let mut count = 0;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            let Some(location) = cell.and_then(|id| ctx.world.locations.get(id)) else {
+                continue;
+            };
+            for link in &location.links {
+                if map_step(ctx.world, link)
+                    .is_some_and(|(dx, dy)| map_cell(&grid, x, y, dx, dy) == Some(link.location))
+                {
+                    continue;
+                }
+                let (Some(link_name), Some(to)) = (
+                    ctx.world.link_names.get(link.link_name),
+                    ctx.world.locations.get(link.location),
+                ) else {
+                    continue;
+                };
+                if count == 0 {
+                    ctx.printer.pl(b"other exits:");
+                }
+                count += 1;
+                ctx.printer.p(&location.name);
+                ctx.printer.p(b": ");
+                ctx.printer.p(&link_name.name);
+                ctx.printer.p(b" to ");
+                ctx.printer.pl(&to.name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn inventory(ctx: &mut ActionContext) -> Result<()> {
+    ctx.printer.p(b"u have: ");
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    if print_objects(ctx.printer, &ctx.world.objects, &entity.objects) == 0 {
+        ctx.printer.p(b"nothing");
+    }
+    ctx.printer.nl();
+
+    let (weight, bulk) = load_of(&ctx.world.objects, &entity.objects);
+    ctx.printer.p(b"load: ");
+    Number::u32(weight).print(ctx.printer);
+    ctx.printer.pb(b'/');
+    Number::u32(entity.max_weight).print(ctx.printer);
+    ctx.printer.p(b" weight, ");
+    Number::u32(bulk).print(ctx.printer);
+    ctx.printer.pb(b'/');
+    Number::u32(entity.max_bulk).print(ctx.printer);
+    ctx.printer.pl(b" bulk");
+
+    Ok(())
+}
+
+pub fn take(ctx: &mut ActionContext) -> Result<()> {
+    // get object name
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"take what");
+        ctx.printer.nlc(2);
+        return Err(Error::TakeWhat);
+    };
+
+    // get optional container name
+    let container_name = match (ctx.tokens.next(), ctx.tokens.next()) {
+        (None, _) => None,
+        (Some(b"from"), Some(container_name)) => Some(container_name),
+        _ => {
+            ctx.printer.p(b"take from what");
+            ctx.printer.nlc(2);
+            return Err(Error::TakeFromWhat);
+        }
+    };
+
+    {
+        let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+        let location_id = entity.location;
+        let location = get(ctx.printer, &ctx.world.locations, location_id)?;
+
+        // find object id and the container holding it
+        let found = if let Some(container_name) = container_name {
+            let container_id = find_container(ctx, container_name)?;
+            let Some((object_id, holder)) = find_nested_object(
+                &ctx.world.objects,
+                &get(ctx.printer, &ctx.world.objects, container_id)?.objects,
+                object_name,
+            ) else {
+                ctx.printer.p(object_name);
+                ctx.printer.p(b" not in ");
+                ctx.printer.p(container_name);
+                ctx.printer.nlc(2);
+                return Err(Error::ObjectNotInContainer);
+            };
+            (object_id, Some(holder.unwrap_or(container_id)))
+        } else {
+            let Some(found) =
+                find_nested_object(&ctx.world.objects, &location.objects, object_name)
+            else {
+                ctx.printer.p(object_name);
+                ctx.printer.p(b" not here");
+                ctx.printer.nlc(2);
+                return Err(Error::ObjectNotHere);
+            };
+            found
+        };
+
+        // note: taking from a container in the inventory adds bulk but no weight
+        let object = get(ctx.printer, &ctx.world.objects, found.0)?;
+        let carried = found.1.is_some_and(|holder| {
+            entity
+                .objects
+                .iter()
+                .any(|&x| x == holder || contains_object(&ctx.world.objects, x, holder))
+        });
+        let weight = if carried {
+            0
+        } else {
+            total_weight(&ctx.world.objects, object)
+        };
+        check_load(ctx.printer, ctx.world, ctx.entity, weight, object.bulk, b"")?;
+
+        let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+        reserve(ctx.printer, &mut entity.objects, 1)?;
+
+        // remove object from location or container
+        let location = get_mut(ctx.printer, &mut ctx.world.locations, location_id)?;
+        detach_object(&mut ctx.world.objects, &mut location.objects, found);
+
+        // add object to entity
+        entity.objects.push(found.0);
+    }
+
+    // send message
+    {
+        let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+        send_message_to_entities_in_location(
+            ctx.world,
+            entity.location,
+            &[ctx.entity],
+            Message::from_parts(&[&entity.name, b" took ", object_name]),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn put(ctx: &mut ActionContext) -> Result<()> {
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"put what");
+        ctx.printer.nlc(2);
+        return Err(Error::PutWhat);
+    };
+
+    let (Some(b"in"), Some(container_name)) = (ctx.tokens.next(), ctx.tokens.next()) else {
+        ctx.printer.p(b"put in what");
+        ctx.printer.nlc(2);
+        return Err(Error::PutInWhat);
+    };
+
+    let Some(found) = find_object_in_entity_inventory(ctx.world, ctx.entity, object_name) else {
+        ctx.printer.p(object_name);
+        ctx.printer.p(b" not in inventory");
+        ctx.printer.nlc(2);
+        return Err(Error::ObjectNotInInventory);
+    };
+
+    let container_id = find_container(ctx, container_name)?;
+
+    // note: a container cannot be put in itself or in a container inside it
+    if container_id == found.0 || contains_object(&ctx.world.objects, found.0, container_id) {
+        ctx.printer.p(b"cannot put ");
+        ctx.printer.p(object_name);
+        ctx.printer.p(b" in itself");
+        ctx.printer.nlc(2);
+        return Err(Error::ContainerCycle);
+    }
+
+    let container = get(ctx.printer, &ctx.world.objects, container_id)?;
+    let (_, contents_bulk) = load_of(&ctx.world.objects, &container.objects);
+    if contents_bulk.saturating_add(get(ctx.printer, &ctx.world.objects, found.0)?.bulk)
+        > container.capacity
+    {
+        ctx.printer.p(container_name);
+        ctx.printer.p(b" is full");
+        ctx.printer.nlc(2);
+        return Err(Error::ContainerFull);
+    }
+
+    reserve(
+        ctx.printer,
+        &mut get_mut(ctx.printer, &mut ctx.world.objects, container_id)?.objects,
+        1,
+    )?;
+
+    // move object from inventory to container
+    let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+    detach_object(&mut ctx.world.objects, &mut entity.objects, found);
+    get_mut(ctx.printer, &mut ctx.world.objects, container_id)?
+        .objects
+        .push(found.0);
+
+    // send message
+    {
+        let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+        send_message_to_entities_in_location(
+            ctx.world,
+            entity.location,
+            &[ctx.entity],
+            Message::from_parts(&[&entity.name, b" put ", object_name, b" in ", container_name]),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn drop(ctx: &mut ActionContext) -> Result<()> {
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"drop what");
+        ctx.printer.nlc(2);
+        return Err(Error::DropWhat);
+    };
+
+    {
+        let Some(found) = find_object_in_entity_inventory(ctx.world, ctx.entity, object_name)
+        else {
+            ctx.printer.p(object_name);
+            ctx.printer.p(b" not in inventory");
+            ctx.printer.nlc(2);
+            return Err(Error::ObjectNotInInventory);
+        };
+
+        let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+        let location = get_mut(ctx.printer, &mut ctx.world.locations, entity.location)?;
+        reserve(ctx.printer, &mut location.objects, 1)?;
+
+        // remove object from entity
+        detach_object(&mut ctx.world.objects, &mut entity.objects, found);
+
+        // add object to location
+        location.objects.push(found.0);
+    }
+
+    // send message
+    {
+        let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+        send_message_to_entities_in_location(
+            ctx.world,
+            entity.location,
+            &[ctx.entity],
+            Message::from_parts(&[&entity.name, b" dropped ", object_name]),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn give(ctx: &mut ActionContext) -> Result<()> {
+    // get entity name
+    let Some(to_entity_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"give to whom");
+        ctx.printer.nlc(2);
+        return Err(Error::GiveToWhom);
+    };
+
+    // get object name
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"give what");
+        ctx.printer.nlc(2);
+        return Err(Error::GiveWhat);
+    };
+
+    let Some(found) = find_object_in_entity_inventory(ctx.world, ctx.entity, object_name) else {
+        ctx.printer.p(object_name);
+        ctx.printer.p(b" not in inventory");
+        ctx.printer.nlc(2);
+        return Err(Error::ObjectNotInInventory);
+    };
+
+    let (entity_name, location_id) = {
+        let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+        (entity.name, entity.location)
+    };
+
+    // find "to" entity
+    let Some(&to_entity_id) = get(ctx.printer, &ctx.world.locations, location_id)?
+        .entities
+        .iter()
+        .find(|&&x| {
+            ctx.world
+                .entities
+                .get(x)
+                .is_some_and(|x| x.name == to_entity_name)
+        })
+    else {
+        ctx.printer.p(to_entity_name);
+        ctx.printer.p(b" not here");
+        ctx.printer.nlc(2);
+        return Err(Error::EntityNotHere);
+    };
+
+    let object = get(ctx.printer, &ctx.world.objects, found.0)?;
+    check_load(
+        ctx.printer,
+        ctx.world,
+        to_entity_id,
+        total_weight(&ctx.world.objects, object),
+        object.bulk,
+        to_entity_name,
+    )?;
+
+    let to_entity = get_mut(ctx.printer, &mut ctx.world.entities, to_entity_id)?;
+    reserve(ctx.printer, &mut to_entity.objects, 1)?;
+
+    // add object to "to" entity
+    to_entity.objects.push(found.0);
+    let to_entity_name = to_entity.name;
+
+    // remove object from entity
+    let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+    detach_object(&mut ctx.world.objects, &mut entity.objects, found);
+
+    // send messages
+    send_message_to_entities_in_location(
+        ctx.world,
+        location_id,
+        &[to_entity_id],
+        Message::from_parts(&[&entity_name, b" gave ", &to_entity_name, b" ", object_name]),
+    );
+
+    send_message_to_entities(
+        ctx.world,
+        &[to_entity_id],
+        Message::from_parts(&[&entity_name, b" gave u ", object_name]),
+    );
+
+    Ok(())
+}
+
+pub fn open(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"open what");
+        ctx.printer.nlc(2);
+        return Err(Error::OpenWhat);
+    };
+
+    let (here_id, index, door) = find_door(ctx, link_name)?;
+    match door.state {
+        DoorState::Open => {
+            return Err(exit_error(
+                ctx.printer,
+                link_name,
+                b" is already open",
+                Error::AlreadyOpen,
+            ));
+        }
+        DoorState::Locked => {
+            return Err(exit_error(
+                ctx.printer,
+                link_name,
+                b" is locked",
+                Error::DoorLocked,
+            ));
+        }
+        DoorState::Closed => {}
+    }
+
+    set_door(
+        ctx.printer,
+        ctx.world,
+        here_id,
+        index,
+        Door {
+            state: DoorState::Open,
+            ..door
+        },
+    )?;
+    send_door_message(ctx, here_id, b" opened ", link_name)
+}
+
+pub fn close(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"close what");
+        ctx.printer.nlc(2);
+        return Err(Error::CloseWhat);
+    };
+
+    let (here_id, index, door) = find_door(ctx, link_name)?;
+    if door.state != DoorState::Open {
+        return Err(exit_error(
+            ctx.printer,
+            link_name,
+            b" is already closed",
+            Error::AlreadyClosed,
+        ));
+    }
+
+    set_door(
+        ctx.printer,
+        ctx.world,
+        here_id,
+        index,
+        Door {
+            state: DoorState::Closed,
+            ..door
+        },
+    )?;
+    send_door_message(ctx, here_id, b" closed ", link_name)
+}
+
+pub fn lock(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"lock what");
+        ctx.printer.nlc(2);
+        return Err(Error::LockWhat);
+    };
+
+    let (here_id, index, door) = find_door(ctx, link_name)?;
+    if door.key.is_none() {
+        return Err(exit_error(
+            ctx.printer,
+            link_name,
+            b" has no lock",
+            Error::NoLock,
+        ));
+    }
+    match door.state {
+        DoorState::Open => {
+            return Err(exit_error(
+                ctx.printer,
+                link_name,
+                b" is open",
+                Error::DoorOpen,
+            ));
+        }
+        DoorState::Locked => {
+            return Err(exit_error(
+                ctx.printer,
+                link_name,
+                b" is already locked",
+                Error::AlreadyLocked,
+            ));
+        }
+        DoorState::Closed => {}
+    }
+    check_key(ctx, door)?;
+
+    set_door(
+        ctx.printer,
+        ctx.world,
+        here_id,
+        index,
+        Door {
+            state: DoorState::Locked,
+            ..door
+        },
+    )?;
+    send_door_message(ctx, here_id, b" locked ", link_name)
+}
+
+pub fn unlock(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"unlock what");
+        ctx.printer.nlc(2);
+        return Err(Error::UnlockWhat);
+    };
+
+    let (here_id, index, door) = find_door(ctx, link_name)?;
+    if door.state != DoorState::Locked {
+        return Err(exit_error(
+            ctx.printer,
+            link_name,
+            b" is not locked",
+            Error::NotLocked,
+        ));
+    }
+    check_key(ctx, door)?;
+
+    set_door(
+        ctx.printer,
+        ctx.world,
+        here_id,
+        index,
+        Door {
+            state: DoorState::Closed,
+            ..door
+        },
+    )?;
+    send_door_message(ctx, here_id, b" unlocked ", link_name)
+}
+
+pub fn memory_info(ctx: &mut ActionContext) -> Result<()> {
+    match ctx.tokens.next() {
+        None => {}
+        Some(b"blocks") => {
+            ctx.printer.p(b"heap blocks:");
+            ctx.printer.nl();
+            ctx.hardware.memory_debug_block_list(ctx.printer);
+            return Ok(());
+        }
+        Some(_) => {
+            ctx.printer.p(b"mi or mi blocks");
+            ctx.printer.nlc(2);
+            return Err(Error::MemoryInfoWhat);
+        }
+    }
+
+    ctx.printer.p(b"   heap start: ");
+    ctx.printer
+        .p_hex_u32(ctx.hardware.memory_heap_start(), true);
+    ctx.printer.nl();
+    ctx.printer.p(b" stack bottom: ");
+    ctx.printer
+        .p_hex_u32(ctx.hardware.memory_stack_bottom(), true);
+    ctx.printer.nl();
+    ctx.printer.p(b"stack pointer: ");
+    ctx.printer
+        .p_hex_u32(ctx.hardware.memory_stack_pointer(), true);
+    ctx.printer.nl();
+    ctx.printer.p(b"   memory end: ");
+    ctx.printer.p_hex_u32(ctx.hardware.memory_end(), true);
+    ctx.printer.nl();
+    ctx.printer.p(b"   stack peak: ");
+    Number::u32(ctx.hardware.memory_stack_peak()).print(ctx.printer);
+    ctx.printer.p(b" of ");
+    Number::u32(ctx.hardware.memory_end() - ctx.hardware.memory_stack_bottom()).print(ctx.printer);
+    ctx.printer.p(b" bytes");
+    ctx.printer.nl();
+    ctx.printer.nl();
+
+    let stats = ctx.hardware.memory_heap_stats();
+    ctx.printer.p(b"  allocations: ");
+    Number::u32(stats.allocations).print(ctx.printer);
+    ctx.printer.nl();
+    ctx.printer.p(b"        frees: ");
+    Number::u32(stats.frees).print(ctx.printer);
+    ctx.printer.nl();
+    ctx.printer.p(b"  heap in use: ");
+    Number::u32(stats.in_use_bytes).print(ctx.printer);
+    ctx.printer.p(b" bytes, peak ");
+    Number::u32(stats.peak_in_use_bytes).print(ctx.printer);
+    ctx.printer.p(b" bytes");
+    ctx.printer.nl();
+    ctx.printer.p(b"    heap free: ");
+    Number::u32(stats.free_bytes).print(ctx.printer);
+    ctx.printer.p(b" bytes in ");
+    Number::u32(stats.free_block_count).print(ctx.printer);
+    ctx.printer.p(b" blocks, largest ");
+    Number::u32(stats.largest_free_block_bytes).print(ctx.printer);
+    ctx.printer.p(b" bytes");
+    ctx.printer.nl();
+    ctx.printer.p(b"fragmentation: ");
+    Number::u32(stats.fragmentation_percent()).print(ctx.printer);
+    ctx.printer.pb(b'%');
+    ctx.printer.nl();
+
+    Ok(())
+}
+
+pub fn heap_check(ctx: &mut ActionContext) -> Result<()> {
+    let errors = ctx.hardware.memory_heap_check(ctx.printer);
+    if errors != 0 {
+        Number::u32(errors).print(ctx.printer);
+        ctx.printer.p(b" heap errors");
+        ctx.printer.nlc(2);
+        return Err(Error::HeapCorrupt);
+    }
+
+    ctx.printer.pl(b"heap ok");
+
+    Ok(())
+}
+
+#[allow(
+    clippy::unnecessary_wraps,
+    reason = "actions return Result for consistency"
+)]
+pub fn sdcard_status(ctx: &mut ActionContext) -> Result<()> {
+    ctx.printer.p(b"SDCARD_STATUS: ");
+    Number::i32(ctx.hardware.sdcard_status()).print(ctx.printer);
+    ctx.printer.nl();
+
+    Ok(())
+}
+
+pub fn sdcard_read(ctx: &mut ActionContext) -> Result<()> {
+    let Some(sector) = ctx.tokens.next().map(u8_slice_to_u32) else {
+        ctx.printer.p(b"what sector");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatSector);
+    };
+
+    let mut buf = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    ctx.hardware.sdcard_read_blocking(sector, &mut buf);
+    ctx.printer.p(&buf);
+    ctx.printer.nl();
+
+    Ok(())
+}
+
+pub fn sdcard_write(ctx: &mut ActionContext) -> Result<()> {
+    let Some(sector) = ctx.tokens.next().map(u8_slice_to_u32) else {
+        ctx.printer.p(b"what sector");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatSector);
+    };
+
+    let data = ctx.tokens.rest();
+    let len = min(data.len(), SDCARD_SECTOR_SIZE_BYTES);
+    let mut buf = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    buf[..len].copy_from_slice(&data[..len]);
+    ctx.hardware.sdcard_write_blocking(sector, &buf);
+
+    Ok(())
+}
+
+pub fn led_set(ctx: &mut ActionContext) -> Result<()> {
+    let bits = if let Some(bits) = ctx.tokens.next() {
+        !u8_slice_bits_to_u32(bits)
+        // note: inverted since '0' is 'on'
+    } else {
+        ctx.printer.p(b"which leds");
+        ctx.printer.nlc(2);
+        return Err(Error::WhichLeds);
+    };
+
+    ctx.hardware.led_set(bits);
+
+    Ok(())
+}
+
+pub fn new_object(ctx: &mut ActionContext) -> Result<()> {
+    // get object name
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what object name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatObjectName);
+    };
+
+    if ctx.world.objects.values().any(|x| x.name == object_name) {
+        ctx.printer.p(b"object already exists");
+        ctx.printer.nlc(2);
+        return Err(Error::ObjectAlreadyExists);
+    }
+
+    reserve_slots(ctx.printer, &mut ctx.world.objects, 1)?;
+    let entity = get_mut(ctx.printer, &mut ctx.world.entities, ctx.entity)?;
+    reserve(ctx.printer, &mut entity.objects, 1)?;
+
+    let object_id = ctx.world.objects.insert(Object {
+        name: Name::from(object_name),
+        description: Note::default(),
+        weight: DEFAULT_WEIGHT,
+        bulk: DEFAULT_BULK,
+        container: false,
+        capacity: DEFAULT_CAPACITY,
+        objects: vec![],
+    });
+
+    entity.objects.push(object_id);
+
+    Ok(())
+}
+
+pub fn new_location(ctx: &mut ActionContext) -> Result<()> {
+    let Some(to_link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what link name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatToLinkName);
+    };
+
+    let Some(back_link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what back link name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatBackLinkName);
+    };
+
+    let Some(new_location_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what new location name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatNewLocationName);
+    };
+
+    if ctx
+        .world
+        .locations
+        .values()
+        .any(|x| x.name == new_location_name)
+    {
+        ctx.printer.p(b"location already exists");
+        ctx.printer.nlc(2);
+        return Err(Error::LocationAlreadyExists);
+    }
+
+    let from_location_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+
+    // note: reserve before changing the world so that it is unchanged when out of memory
+    let mut links = Vec::new();
+    reserve(ctx.printer, &mut links, 1)?;
+    reserve_slots(ctx.printer, &mut ctx.world.link_names, 2)?;
+    reserve_slots(ctx.printer, &mut ctx.world.locations, 1)?;
+    reserve(
+        ctx.printer,
+        &mut get_mut(ctx.printer, &mut ctx.world.locations, from_location_id)?.links,
+        1,
+    )?;
+
+    let to_link_name_id = find_or_add_link(ctx.world, to_link_name);
+
+    // check if link is already used
+    if get(ctx.printer, &ctx.world.locations, from_location_id)?
+        .links
+        .iter()
+        .any(|x| x.link_name == to_link_name_id)
+    {
+        ctx.printer.p(b"link from this location already exists");
+        ctx.printer.nlc(2);
+        return Err(Error::LinkFromLocationAlreadyExists);
+    }
+
+    let back_link_name_id = find_or_add_link(ctx.world, back_link_name);
+
+    // add location and link it back to from location
+    links.push(Link {
+        link_name: back_link_name_id,
+        location: from_location_id,
+        door: None,
+    });
+    let new_location_id = ctx.world.locations.insert(Location {
+        name: Name::from(new_location_name),
+        note: Note::default(),
+        links,
+        objects: vec![],
+        entities: vec![],
+    });
+
+    get_mut(ctx.printer, &mut ctx.world.locations, from_location_id)?
+        .links
+        .push(Link {
+            link_name: to_link_name_id,
+            location: new_location_id,
+            door: None,
+        });
+
+    Ok(())
+}
+
+pub fn new_entity(ctx: &mut ActionContext) -> Result<()> {
+    // get entity name
+    let Some(entity_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what entity name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatEntityName);
+    };
+
+    if ctx.world.entities.values().any(|x| x.name == entity_name) {
+        ctx.printer.p(b"entity already exists");
+        ctx.printer.nlc(2);
+        return Err(Error::EntityAlreadyExists);
+    }
+
+    let location_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    reserve_slots(ctx.printer, &mut ctx.world.entities, 1)?;
+    let location = get_mut(ctx.printer, &mut ctx.world.locations, location_id)?;
+    reserve(ctx.printer, &mut location.entities, 1)?;
+
+    let entity_id = ctx.world.entities.insert(Entity {
+        name: Name::from(entity_name),
+        description: Note::default(),
+        location: location_id,
+        objects: vec![],
+        messages: vec![],
+        max_weight: DEFAULT_MAX_WEIGHT,
+        max_bulk: DEFAULT_MAX_BULK,
+    });
+    location.entities.push(entity_id);
+
+    Ok(())
+}
+
+pub fn remove_object(ctx: &mut ActionContext) -> Result<()> {
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"remove what object");
+        ctx.printer.nlc(2);
+        return Err(Error::RemoveObjectWhat);
+    };
+
+    let Some(object_id) = ctx.world.objects.find(|x| x.name == object_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            object_name,
+            Error::NoSuchObject,
+        ));
+    };
+
+    // note: object is in a location, an inventory or a container
+    for x in ctx.world.locations.values_mut() {
+        x.objects.retain(|&x| x != object_id);
+    }
+    for x in ctx.world.entities.values_mut() {
+        x.objects.retain(|&x| x != object_id);
+    }
+    for x in ctx.world.objects.values_mut() {
+        x.objects.retain(|&x| x != object_id);
+    }
+    remove_object_and_contents(&mut ctx.world.objects, object_id);
+    forget_removed_keys(ctx.world);
+
+    Ok(())
+}
+
+pub fn remove_location(ctx: &mut ActionContext) -> Result<()> {
+    let Some(location_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"remove what location");
+        ctx.printer.nlc(2);
+        return Err(Error::RemoveLocationWhat);
+    };
+
+    let Some(location_id) = ctx.world.locations.find(|x| x.name == location_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            location_name,
+            Error::NoSuchLocation,
+        ));
+    };
+
+    let here_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    if location_id == here_id {
+        ctx.printer.p(b"cannot remove location u r in");
+        ctx.printer.nlc(2);
+        return Err(Error::CannotRemoveCurrentLocation);
+    }
+
+    // note: reserve before changing the world so that it is unchanged when out of memory
+    let entities_count = get(ctx.printer, &ctx.world.locations, location_id)?
+        .entities
+        .len();
+    let here = get_mut(ctx.printer, &mut ctx.world.locations, here_id)?;
+    reserve(ctx.printer, &mut here.entities, entities_count)?;
+
+    let Some(location) = ctx.world.locations.remove(location_id) else {
+        return Err(stale_id(ctx.printer));
+    };
+
+    // move entities in the location here
+    // note: told before they are added so that they do not see each other arrive
+    for &eid in &location.entities {
+        let Some(x) = ctx.world.entities.get_mut(eid) else {
+            continue;
+        };
+        x.location = here_id;
+        let message = Message::from_parts(&[&x.name, b" arrived"]);
+        send_message_to_entities_in_location(ctx.world, here_id, &[ctx.entity], message);
+    }
+    if let Some(here) = ctx.world.locations.get_mut(here_id) {
+        here.entities.extend_from_slice(&location.entities);
+        let message = Message::from_parts(&[&location.name, b" vanished, u r in ", &here.name]);
+        send_message_to_entities(ctx.world, &location.entities, message);
+    }
+
+    // objects in the location are removed with it
+    for &oid in &location.objects {
+        remove_object_and_contents(&mut ctx.world.objects, oid);
+    }
+
+    // remove links to the location
+    for x in ctx.world.locations.values_mut() {
+        x.links.retain(|x| x.location != location_id);
+    }
+    forget_removed_keys(ctx.world);
+
+    Ok(())
+}
+
+pub fn remove_entity(ctx: &mut ActionContext) -> Result<()> {
+    let Some(entity_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"remove what entity");
+        ctx.printer.nlc(2);
+        return Err(Error::RemoveEntityWhat);
+    };
+
+    let Some(entity_id) = ctx.world.entities.find(|x| x.name == entity_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            entity_name,
+            Error::NoSuchEntity,
+        ));
+    };
+
+    if entity_id == ctx.entity {
+        ctx.printer.p(b"cannot remove urself");
+        ctx.printer.nlc(2);
+        return Err(Error::CannotRemoveSelf);
+    }
+
+    let Some(entity) = ctx.world.entities.remove(entity_id) else {
+        return Err(stale_id(ctx.printer));
+    };
+
+    // inventory is removed with the entity
+    for &oid in &entity.objects {
+        remove_object_and_contents(&mut ctx.world.objects, oid);
+    }
+
+    if let Some(location) = ctx.world.locations.get_mut(entity.location) {
+        location.entities.retain(|&x| x != entity_id);
+    }
+    forget_removed_keys(ctx.world);
+
+    Ok(())
+}
+
+pub fn unlink(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"unlink what exit");
+        ctx.printer.nlc(2);
+        return Err(Error::UnlinkWhat);
+    };
+
+    let (here_id, index) = find_exit(ctx, link_name)?;
+    let back_index = find_back_link(ctx.world, here_id, index);
+    let to_location_id = get_mut(ctx.printer, &mut ctx.world.locations, here_id)?
+        .links
+        .remove(index)
+        .location;
+
+    // remove the link back, other exits between the locations are kept
+    if let Some(x) = ctx.world.locations.get_mut(to_location_id)
+        && let Some(i) = back_index
+    {
+        x.links.remove(i);
+    }
+
+    Ok(())
+}
+
+pub fn new_link(ctx: &mut ActionContext) -> Result<()> {
+    let (Some(link_name), Some(location_name)) = (ctx.tokens.next(), ctx.tokens.next()) else {
+        ctx.printer.p(b"link what exit to what location");
+        ctx.printer.nlc(2);
+        return Err(Error::LinkWhat);
+    };
+
+    let Some(to_location_id) = ctx.world.locations.find(|x| x.name == location_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            location_name,
+            Error::NoSuchLocation,
+        ));
+    };
+
+    let here_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    let here = get(ctx.printer, &ctx.world.locations, here_id)?;
+    if ctx
+        .world
+        .link_names
+        .find(|x| x.name == link_name)
+        .is_some_and(|id| here.links.iter().any(|x| x.link_name == id))
+    {
+        ctx.printer.p(b"link from this location already exists");
+        ctx.printer.nlc(2);
+        return Err(Error::LinkFromLocationAlreadyExists);
+    }
+
+    // note: reserve before changing the world so that it is unchanged when out of memory
+    reserve_slots(ctx.printer, &mut ctx.world.link_names, 1)?;
+    reserve(
+        ctx.printer,
+        &mut get_mut(ctx.printer, &mut ctx.world.locations, here_id)?.links,
+        1,
+    )?;
+
+    let link_name_id = find_or_add_link(ctx.world, link_name);
+    get_mut(ctx.printer, &mut ctx.world.locations, here_id)?
+        .links
+        .push(Link {
+            link_name: link_name_id,
+            location: to_location_id,
+            door: None,
+        });
+
+    Ok(())
+}
+
+pub fn new_container(ctx: &mut ActionContext) -> Result<()> {
+    let Some(object_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"container what object");
+        ctx.printer.nlc(2);
+        return Err(Error::ContainerWhat);
+    };
+
+    let Some(object_id) = ctx.world.objects.find(|x| x.name == object_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            object_name,
+            Error::NoSuchObject,
+        ));
+    };
+
+    get_mut(ctx.printer, &mut ctx.world.objects, object_id)?.container = true;
+
+    Ok(())
+}
+
+pub fn set_object(ctx: &mut ActionContext) -> Result<()> {
+    let (Some(object_name), Some(weight), Some(bulk)) =
+        (ctx.tokens.next(), ctx.tokens.next(), ctx.tokens.next())
+    else {
+        ctx.printer.p(b"set what object weight and bulk");
+        ctx.printer.nlc(2);
+        return Err(Error::SetObjectWhat);
+    };
+    let capacity = ctx.tokens.next();
+
+    let weight = number(ctx.printer, weight)?;
+    let bulk = number(ctx.printer, bulk)?;
+    let capacity = capacity.map(|x| number(ctx.printer, x)).transpose()?;
+
+    let Some(object_id) = ctx.world.objects.find(|x| x.name == object_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            object_name,
+            Error::NoSuchObject,
+        ));
+    };
+
+    let object = get_mut(ctx.printer, &mut ctx.world.objects, object_id)?;
+    object.weight = weight;
+    object.bulk = bulk;
+    if let Some(capacity) = capacity {
+        object.capacity = capacity;
+    }
+
+    Ok(())
+}
+
+pub fn set_entity(ctx: &mut ActionContext) -> Result<()> {
+    let (Some(entity_name), Some(max_weight), Some(max_bulk)) =
+        (ctx.tokens.next(), ctx.tokens.next(), ctx.tokens.next())
+    else {
+        ctx.printer.p(b"set what entity max weight and bulk");
+        ctx.printer.nlc(2);
+        return Err(Error::SetEntityWhat);
+    };
+
+    let max_weight = number(ctx.printer, max_weight)?;
+    let max_bulk = number(ctx.printer, max_bulk)?;
+
+    let Some(entity_id) = ctx.world.entities.find(|x| x.name == entity_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            entity_name,
+            Error::NoSuchEntity,
+        ));
+    };
+
+    // note: an entity already carrying more keeps it but cannot take more
+    let entity = get_mut(ctx.printer, &mut ctx.world.entities, entity_id)?;
+    entity.max_weight = max_weight;
+    entity.max_bulk = max_bulk;
+
+    Ok(())
+}
+
+pub fn new_door(ctx: &mut ActionContext) -> Result<()> {
+    let Some(link_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"door on what exit");
+        ctx.printer.nlc(2);
+        return Err(Error::DoorWhat);
+    };
+
+    // note: a door with a key starts locked
+    let door = if let Some(key_name) = ctx.tokens.next() {
+        let Some(key) = ctx.world.objects.find(|x| x.name == key_name) else {
+            return Err(does_not_exist(ctx.printer, key_name, Error::NoSuchObject));
+        };
+        Door {
+            state: DoorState::Locked,
+            key: Some(key),
+        }
+    } else {
+        Door {
+            state: DoorState::Closed,
+            key: None,
+        }
+    };
+
+    let (here_id, index) = find_exit(ctx, link_name)?;
+    set_door(ctx.printer, ctx.world, here_id, index, door)
+}
+
+pub fn rename(ctx: &mut ActionContext) -> Result<()> {
+    let Some(kind) = ctx
+        .tokens
+        .next()
+        .filter(|x| matches!(*x, b"object" | b"location" | b"entity"))
+    else {
+        ctx.printer.p(b"rename object, location or entity");
+        ctx.printer.nlc(2);
+        return Err(Error::RenameWhat);
+    };
+
+    let Some(old_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"rename what ");
+        ctx.printer.p(kind);
+        ctx.printer.nlc(2);
+        return Err(Error::RenameWhat);
+    };
+
+    let Some(new_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"what new name");
+        ctx.printer.nlc(2);
+        return Err(Error::WhatNewName);
+    };
+
+    // note: names are unique per kind as checked when created
+    match kind {
+        b"object" => {
+            let Some(id) = ctx.world.objects.find(|x| x.name == old_name) else {
+                return Err(does_not_exist(ctx.printer, old_name, Error::NoSuchObject));
+            };
+            if ctx.world.objects.values().any(|x| x.name == new_name) {
+                ctx.printer.p(b"object already exists");
+                ctx.printer.nlc(2);
+                return Err(Error::ObjectAlreadyExists);
+            }
+            get_mut(ctx.printer, &mut ctx.world.objects, id)?.name = Name::from(new_name);
+        }
+        b"location" => {
+            let Some(id) = ctx.world.locations.find(|x| x.name == old_name) else {
+                return Err(does_not_exist(ctx.printer, old_name, Error::NoSuchLocation));
+            };
+            if ctx.world.locations.values().any(|x| x.name == new_name) {
+                ctx.printer.p(b"location already exists");
+                ctx.printer.nlc(2);
+                return Err(Error::LocationAlreadyExists);
+            }
+            get_mut(ctx.printer, &mut ctx.world.locations, id)?.name = Name::from(new_name);
+        }
+        _ => {
+            let Some(id) = ctx.world.entities.find(|x| x.name == old_name) else {
+                return Err(does_not_exist(ctx.printer, old_name, Error::NoSuchEntity));
+            };
+            if ctx.world.entities.values().any(|x| x.name == new_name) {
+                ctx.printer.p(b"entity already exists");
+                ctx.printer.nlc(2);
+                return Err(Error::EntityAlreadyExists);
+            }
+            get_mut(ctx.printer, &mut ctx.world.entities, id)?.name = Name::from(new_name);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn describe(ctx: &mut ActionContext) -> Result<()> {
+    let Some(name) = ctx.tokens.next() else {
+        ctx.printer.p(b"describe what");
+        ctx.printer.nlc(2);
+        return Err(Error::DescribeWhat);
+    };
+
+    let description = Note::from(ctx.tokens.rest());
+    match find_thing(ctx, name)? {
+        Thing::Object(id) => {
+            get_mut(ctx.printer, &mut ctx.world.objects, id)?.description = description;
+        }
+        Thing::Entity(id) => {
+            get_mut(ctx.printer, &mut ctx.world.entities, id)?.description = description;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn examine(ctx: &mut ActionContext) -> Result<()> {
+    let Some(name) = ctx.tokens.next() else {
+        ctx.printer.p(b"examine what");
+        ctx.printer.nlc(2);
+        return Err(Error::ExamineWhat);
+    };
+
+    let (description, container) = match find_thing(ctx, name)? {
+        Thing::Object(id) => {
+            let object = get(ctx.printer, &ctx.world.objects, id)?;
+            (&object.description, object.container.then_some(object))
+        }
+        Thing::Entity(id) => (
+            &get(ctx.printer, &ctx.world.entities, id)?.description,
+            None,
+        ),
+    };
+
+    if description.is_empty() {
+        ctx.printer.p(b"u c nothing special about ");
+        ctx.printer.pl(name);
+    } else {
+        ctx.printer.pl(description);
+    }
+
+    if let Some(container) = container {
+        ctx.printer.p(b"in it: ");
+        if print_objects(ctx.printer, &ctx.world.objects, &container.objects) == 0 {
+            ctx.printer.p(b"nothing");
+        }
+        ctx.printer.nl();
+    }
+
+    Ok(())
+}
+
+pub fn set_location_note(ctx: &mut ActionContext) -> Result<()> {
+    let location_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    get_mut(ctx.printer, &mut ctx.world.locations, location_id)?.note =
+        Note::from(ctx.tokens.rest());
+
+    Ok(())
+}
+
+pub fn say(ctx: &mut ActionContext) -> Result<()> {
+    let say = ctx.tokens.rest();
+    if say.is_empty() {
+        ctx.printer.p(b"say what");
+        ctx.printer.nlc(2);
+        return Err(Error::SayWhat);
+    }
+
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    if !send_message_to_entities_in_location(
+        ctx.world,
+        entity.location,
+        &[ctx.entity],
+        Message::from_parts(&[&entity.name, b" says ", say]),
+    ) {
+        ctx.printer.p(b"out of memory");
+        ctx.printer.nlc(2);
+        return Err(Error::OutOfMemory);
+    }
+
+    Ok(())
+}
+
+pub fn tell(ctx: &mut ActionContext) -> Result<()> {
+    let Some(to_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"tell to whom");
+        ctx.printer.nlc(2);
+        return Err(Error::TellToWhom);
+    };
+
+    let tell = ctx.tokens.rest();
+    if tell.is_empty() {
+        ctx.printer.p(b"tell what");
+        ctx.printer.nlc(2);
+        return Err(Error::TellWhat);
+    }
+
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+
+    let Some(&to_entity_id) = get(ctx.printer, &ctx.world.locations, entity.location)?
+        .entities
+        .iter()
+        .find(|&&x| ctx.world.entities.get(x).is_some_and(|x| x.name == to_name))
+    else {
+        ctx.printer.p(to_name);
+        ctx.printer.p(b" not here");
+        ctx.printer.nlc(2);
+        return Err(Error::EntityNotHere);
+    };
+
+    let message = Message::from_parts(&[&entity.name, b" tells u ", tell]);
+    let to_entity = get_mut(ctx.printer, &mut ctx.world.entities, to_entity_id)?;
+    reserve(ctx.printer, &mut to_entity.messages, 1)?;
+    to_entity.messages.push(message);
+
+    Ok(())
+}
+
+pub fn save(ctx: &mut ActionContext) -> Result<()> {
+    if let Err(e) = persistence::save(ctx.world, ctx.hardware) {
+        print_persistence_error(ctx, &e);
+        return Err(Error::SaveFailed);
+    }
+
+    ctx.printer.pl(b"world saved");
+
+    Ok(())
+}
+
+pub fn load(ctx: &mut ActionContext) -> Result<()> {
+    match persistence::load(ctx.hardware) {
+        Ok(world) => *ctx.world = world,
+        Err(e) => {
+            print_persistence_error(ctx, &e);
+            return Err(Error::LoadFailed);
+        }
+    }
+
+    ctx.printer.pl(b"world loaded");
+
+    Ok(())
+}
+
+pub fn export(ctx: &mut ActionContext) -> Result<()> {
+    match ctx.tokens.next() {
+        Some(b"dot") => export::dot(ctx.printer, ctx.world),
+        Some(b"json") => export::json(ctx.printer, ctx.world),
+        _ => {
+            ctx.printer.p(b"export dot or json");
+            ctx.printer.nlc(2);
+            return Err(Error::ExportWhat);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn list(ctx: &mut ActionContext) -> Result<()> {
+    let path = ctx.tokens.next().unwrap_or(b".");
+
+    let result = fat::mount(ctx.hardware).and_then(|volume| {
+        let entry = volume.resolve(ctx.hardware, *ctx.working_directory, path)?;
+        if !entry.is_directory() {
+            print_file_entry(ctx.printer, &entry);
+            return Ok(());
+        }
+        volume.for_each_entry(ctx.hardware, entry.cluster, |x| {
+            if !x.is_dot() {
+                print_file_entry(ctx.printer, x);
+            }
+            ControlFlow::Continue(())
+        })
+    });
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::ListFailed);
+    }
+
+    Ok(())
+}
+
+pub fn cat(ctx: &mut ActionContext) -> Result<()> {
+    let Some(path) = ctx.tokens.next() else {
+        ctx.printer.p(b"cat what");
+        ctx.printer.nlc(2);
+        return Err(Error::CatWhat);
+    };
+
+    let mut last = b'\n';
+    let result = fat::mount(ctx.hardware).and_then(|volume| {
+        let entry = volume.resolve(ctx.hardware, *ctx.working_directory, path)?;
+        volume.read(ctx.hardware, &entry, |chunk| {
+            for &byte in chunk {
+                match byte {
+                    b'\n' => ctx.printer.nl(),
+                    b'\r' => {}
+                    _ => ctx.printer.pb(byte),
+                }
+                last = byte;
+            }
+        })
+    });
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::CatFailed);
+    }
+
+    if last != b'\n' {
+        ctx.printer.nl();
+    }
+
+    Ok(())
+}
+
+pub fn change_directory(ctx: &mut ActionContext) -> Result<()> {
+    let path = ctx.tokens.next().unwrap_or(b"/");
+
+    let result = fat::mount(ctx.hardware).and_then(|volume| {
+        let entry = volume.resolve(ctx.hardware, *ctx.working_directory, path)?;
+        if !entry.is_directory() {
+            return Err(fat::Error::NotADirectory);
+        }
+        Ok(entry.cluster)
+    });
+
+    match result {
+        Ok(cluster) => *ctx.working_directory = cluster,
+        Err(e) => {
+            print_fat_error(ctx, path, &e);
+            return Err(Error::ChangeDirectoryFailed);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_file(ctx: &mut ActionContext) -> Result<()> {
+    let Some(path) = ctx.tokens.next() else {
+        ctx.printer.p(b"write to what");
+        ctx.printer.nlc(2);
+        return Err(Error::WriteToWhat);
+    };
+
+    // note: without text the file is truncated
+    let text = ctx.tokens.rest();
+    let data = if text.is_empty() {
+        vec![]
+    } else {
+        [text, b"\n"].concat()
+    };
+
+    let result = fat::mount(ctx.hardware)
+        .and_then(|mut volume| volume.write(ctx.hardware, *ctx.working_directory, path, &data));
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::WriteFailed);
+    }
+
+    Ok(())
+}
+
+pub fn append_file(ctx: &mut ActionContext) -> Result<()> {
+    let Some(path) = ctx.tokens.next() else {
+        ctx.printer.p(b"append to what");
+        ctx.printer.nlc(2);
+        return Err(Error::AppendToWhat);
+    };
+
+    let text = ctx.tokens.rest();
+    if text.is_empty() {
+        ctx.printer.p(b"append what");
+        ctx.printer.nlc(2);
+        return Err(Error::AppendWhat);
+    }
+
+    let data = [text, b"\n"].concat();
+    let result = fat::mount(ctx.hardware)
+        .and_then(|mut volume| volume.append(ctx.hardware, *ctx.working_directory, path, &data));
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::AppendFailed);
+    }
+
+    Ok(())
+}
+
+pub fn remove_file(ctx: &mut ActionContext) -> Result<()> {
+    let Some(path) = ctx.tokens.next() else {
+        ctx.printer.p(b"remove what");
+        ctx.printer.nlc(2);
+        return Err(Error::RemoveWhat);
+    };
+
+    let result = fat::mount(ctx.hardware)
+        .and_then(|mut volume| volume.remove(ctx.hardware, *ctx.working_directory, path));
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::RemoveFailed);
+    }
+
+    Ok(())
+}
+
+pub fn make_directory(ctx: &mut ActionContext) -> Result<()> {
+    let Some(path) = ctx.tokens.next() else {
+        ctx.printer.p(b"make what directory");
+        ctx.printer.nlc(2);
+        return Err(Error::MakeDirectoryWhat);
+    };
+
+    let result = fat::mount(ctx.hardware)
+        .and_then(|mut volume| volume.make_directory(ctx.hardware, *ctx.working_directory, path));
+
+    if let Err(e) = result {
+        print_fat_error(ctx, path, &e);
+        return Err(Error::MakeDirectoryFailed);
+    }
+
+    Ok(())
+}
+
+#[allow(
+    clippy::unnecessary_wraps,
+    reason = "actions return Result for consistency"
+)]
+pub const fn wait(_ctx: &mut ActionContext) -> Result<()> {
+    Ok(())
+}
+
+//
+// utilities
+//
+
+/// Object found by name: its id and the container holding it or `None` if it is in the searched
+/// list.
+type FoundObject = (ObjectId, Option<ObjectId>);
+
+fn find_object_in_entity_inventory(
+    world: &World,
+    entity: EntityId,
+    object_name: &[u8],
+) -> Option<FoundObject> {
+    find_nested_object(
+        &world.objects,
+        &world.entities.get(entity)?.objects,
+        object_name,
+    )
+}
+
+/// Returns the object named `name` in `list` or nested in the containers in it.
+fn find_nested_object(
+    objects: &Arena<Object>,
+    list: &[ObjectId],
+    name: &[u8],
+) -> Option<FoundObject> {
+    for &oid in list {
+        let Some(x) = objects.get(oid) else {
+            continue;
+        };
+        if x.name == name {
+            return Some((oid, None));
+        }
+        if let Some((id, holder)) = find_nested_object(objects, &x.objects, name) {
+            return Some((id, Some(holder.unwrap_or(oid))));
+        }
+    }
+
+    None
+}
+
+/// Removes the found object from the container holding it or from `list`.
+fn detach_object(
+    objects: &mut Arena<Object>,
+    list: &mut Vec<ObjectId>,
+    (object_id, holder): FoundObject,
+) {
+    let list = match holder.map(|x| objects.get_mut(x)) {
+        Some(Some(x)) => &mut x.objects,
+        Some(None) => return,
+        None => list,
+    };
+    // note: first occurrence since the object may have been added to the same list
+    if let Some(index) = list.iter().position(|&x| x == object_id) {
+        list.remove(index);
+    }
+}
+
+/// Returns true if `object_id` is in the container `container_id` or nested in the containers in
+/// it.
+fn contains_object(objects: &Arena<Object>, container_id: ObjectId, object_id: ObjectId) -> bool {
+    objects.get(container_id).is_some_and(|x| {
+        x.objects
+            .iter()
+            .any(|&oid| oid == object_id || contains_object(objects, oid, object_id))
+    })
+}
+
+/// Weight of the object and the objects in it.
+fn total_weight(objects: &Arena<Object>, object: &Object) -> u32 {
+    object
+        .objects
+        .iter()
+        .filter_map(|&x| objects.get(x))
+        .fold(object.weight, |weight, x| {
+            weight.saturating_add(total_weight(objects, x))
+        })
+}
+
+/// Weight and bulk of the objects in `list`. Weight includes the objects in containers while
+/// bulk does not.
+fn load_of(objects: &Arena<Object>, list: &[ObjectId]) -> (u32, u32) {
+    list.iter()
+        .filter_map(|&x| objects.get(x))
+        .fold((0, 0), |(weight, bulk), x| {
+            (
+                weight.saturating_add(total_weight(objects, x)),
+                bulk.saturating_add(x.bulk),
+            )
+        })
+}
+
+/// Returns an error if entity `entity_id` cannot carry `weight` and `bulk` more. The refusal
+/// names the entity when `name` is not empty.
+fn check_load(
+    printer: &dyn Printer,
+    world: &World,
+    entity_id: EntityId,
+    weight: u32,
+    bulk: u32,
+    name: &[u8],
+) -> Result<()> {
+    let entity = get(printer, &world.entities, entity_id)?;
+    let (carried_weight, carried_bulk) = load_of(&world.objects, &entity.objects);
+
+    let (message, error): (&[u8], _) = if carried_weight.saturating_add(weight) > entity.max_weight
+    {
+        (b"too heavy", Error::TooHeavy)
+    } else if carried_bulk.saturating_add(bulk) > entity.max_bulk {
+        (b"hands full", Error::HandsFull)
+    } else {
+        return Ok(());
+    };
+
+    printer.p(message);
+    if !name.is_empty() {
+        printer.p(b" for ");
+        printer.p(name);
+    }
+    printer.nlc(2);
+    Err(error)
+}
+
+/// Removes the object and the objects in it.
+fn remove_object_and_contents(objects: &mut Arena<Object>, object_id: ObjectId) {
+    if let Some(x) = objects.remove(object_id) {
+        for &oid in &x.objects {
+            remove_object_and_contents(objects, oid);
+        }
+    }
+}
+
+/// Drops the keys of doors that have been removed.
+/// note: a locked door is left closed since it can no longer be unlocked
+fn forget_removed_keys(world: &mut World) {
+    for door in world
+        .locations
+        .values_mut()
+        .flat_map(|x| &mut x.links)
+        .filter_map(|x| x.door.as_mut())
+    {
+        if door.key.is_some_and(|x| !world.objects.contains(x)) {
+            door.key = None;
+            if door.state == DoorState::Locked {
+                door.state = DoorState::Closed;
+            }
+        }
+    }
+}
+
+/// Returns the container named `name` in the inventory or location of the acting entity.
+fn find_container(ctx: &ActionContext, name: &[u8]) -> Result<ObjectId> {
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    let location = get(ctx.printer, &ctx.world.locations, entity.location)?;
+
+    let Some((container_id, _)) = find_nested_object(&ctx.world.objects, &entity.objects, name)
+        .or_else(|| find_nested_object(&ctx.world.objects, &location.objects, name))
+    else {
+        ctx.printer.p(name);
+        ctx.printer.p(b" not here");
+        ctx.printer.nlc(2);
+        return Err(Error::ObjectNotHere);
+    };
+
+    if !get(ctx.printer, &ctx.world.objects, container_id)?.container {
+        ctx.printer.p(name);
+        ctx.printer.p(b" is not a container");
+        ctx.printer.nlc(2);
+        return Err(Error::NotAContainer);
+    }
+
+    Ok(container_id)
+}
+
+/// Prints the name of `object` followed by its contents in parentheses, if any.
+fn print_object(printer: &dyn Printer, objects: &Arena<Object>, object: &Object) {
+    printer.p(&object.name);
+    if !object.objects.is_empty() {
+        printer.p(b" (");
+        print_objects(printer, objects, &object.objects);
+        printer.pb(b')');
+    }
+}
+
+/// Prints the objects separated by commas and returns how many were printed.
+fn print_objects(printer: &dyn Printer, objects: &Arena<Object>, list: &[ObjectId]) -> usize {
+    let mut count = 0;
+    for x in list.iter().filter_map(|&oid| objects.get(oid)) {
+        if count != 0 {
+            printer.p(b", ");
+        }
+        count += 1;
+        print_object(printer, objects, x);
+    }
+    count
+}
+
+/// Returns the location of the acting entity and the index of its exit named `link_name`.
+fn find_exit(ctx: &ActionContext, link_name: &[u8]) -> Result<(LocationId, usize)> {
+    let here_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    let here = get(ctx.printer, &ctx.world.locations, here_id)?;
+
+    let Some(index) = ctx
+        .world
+        .link_names
+        .find(|x| x.name == link_name)
+        .and_then(|id| here.links.iter().position(|x| x.link_name == id))
+    else {
+        return Err(exit_error(
+            ctx.printer,
+            link_name,
+            b" is not an exit",
+            Error::NoSuchExit,
+        ));
+    };
+
+    Ok((here_id, index))
+}
+
+/// Same as `find_exit` and also returns the door of the exit.
+fn find_door(ctx: &ActionContext, link_name: &[u8]) -> Result<(LocationId, usize, Door)> {
+    let (here_id, index) = find_exit(ctx, link_name)?;
+    let Some(door) = get(ctx.printer, &ctx.world.locations, here_id)?.links[index].door else {
+        return Err(exit_error(
+            ctx.printer,
+            link_name,
+            b" is not a door",
+            Error::NotADoor,
+        ));
+    };
+
+    Ok((here_id, index, door))
+}
+
+/// Sets `door` on link `index` of location `location_id` and on the link back, if any.
+fn set_door(
+    printer: &dyn Printer,
+    world: &mut World,
+    location_id: LocationId,
+    index: usize,
+    door: Door,
+) -> Result<()> {
+    let back_index = find_back_link(world, location_id, index);
+    let link = &mut get_mut(printer, &mut world.locations, location_id)?.links[index];
+    link.door = Some(door);
+    let to_location_id = link.location;
+
+    // note: the door is seen from both sides
+    if let Some(x) = world
+        .locations
+        .get_mut(to_location_id)
+        .and_then(|x| x.links.get_mut(back_index?))
+    {
+        x.door = Some(door);
+    }
+
+    Ok(())
+}
+
+/// Returns the number in `bytes` or prints that it is not a number.
+fn number(printer: &dyn Printer, bytes: &[u8]) -> Result<u32> {
+    u8_slice_to_u32_checked(bytes).ok_or_else(|| {
+        printer.p(bytes);
+        printer.p(b" is not a number");
+        printer.nlc(2);
+        Error::NotANumber
+    })
+}
+
+/// Link names that are the way back of each other.
+const OPPOSITE_LINK_NAMES: [(&[u8], &[u8]); 11] = [
+    (b"north", b"south"),
+    (b"east", b"west"),
+    (b"northeast", b"southwest"),
+    (b"northwest", b"southeast"),
+    (b"up", b"down"),
+    (b"in", b"out"),
+    (b"n", b"s"),
+    (b"e", b"w"),
+    (b"ne", b"sw"),
+    (b"nw", b"se"),
+    (b"u", b"d"),
+];
+
+/// Returns true if the link names are the opposite of each other such as north and south.
+fn opposite_link_names(world: &World, a: LinkNameId, b: LinkNameId) -> bool {
+    let (Some(a), Some(b)) = (world.link_names.get(a), world.link_names.get(b)) else {
+        return false;
+    };
+    OPPOSITE_LINK_NAMES
+        .iter()
+        .any(|&(x, y)| (a.name == x && b.name == y) || (a.name == y && b.name == x))
+}
+
+/// Returns the index of the link that leads back from where link `index` of `from_location_id`
+/// leads to or `None` if the link is one-way.
+///
+/// note: a link back with the opposite name is taken first, other links between the two
+///       locations are paired in order
+fn find_back_link(world: &World, from_location_id: LocationId, index: usize) -> Option<usize> {
+    let from_links = &world.locations.get(from_location_id)?.links;
+    let link = from_links.get(index)?;
+    let to_location_id = link.location;
+    let to_links = &world.locations.get(to_location_id)?.links;
+
+    let leads_back = |x: &Link| x.location == from_location_id;
+    if let Some(i) = to_links
+        .iter()
+        .position(|x| leads_back(x) && opposite_link_names(world, link.link_name, x.link_name))
+    {
+        return Some(i);
+    }
+
+    // links that are not paired by name, in order on both sides
+    let unpaired = |x: &Link, others: &[Link], to_id| {
+        !others
+            .iter()
+            .any(|y| y.location == to_id && opposite_link_names(world, x.link_name, y.link_name))
+    };
+    let rank = from_links[..index]
+        .iter()
+        .filter(|x| x.location == to_location_id && unpaired(x, to_links, from_location_id))
+        .count();
+    to_links
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| leads_back(x) && unpaired(x, from_links, to_location_id))
+        .nth(rank)
+        .map(|(i, _)| i)
+}
+
+/// Returns `Error::NoKey` if the key of `door` is not in the inventory of the acting entity or
+/// nested in the containers in it.
+fn check_key(ctx: &ActionContext, door: Door) -> Result<()> {
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    let has_key = door.key.is_some_and(|key| {
+        entity
+            .objects
+            .iter()
+            .any(|&x| x == key || contains_object(&ctx.world.objects, x, key))
+    });
+    if !has_key {
+        ctx.printer.p(b"u do not have the key");
+        ctx.printer.nlc(2);
+        return Err(Error::NoKey);
+    }
+
+    Ok(())
+}
+
+/// Sends "<entity><what><link name>" to the other entities in `location`.
+fn send_door_message(
+    ctx: &mut ActionContext,
+    location: LocationId,
+    what: &[u8],
+    link_name: &[u8],
+) -> Result<()> {
+    let entity_name = get(ctx.printer, &ctx.world.entities, ctx.entity)?.name;
+    send_message_to_entities_in_location(
+        ctx.world,
+        location,
+        &[ctx.entity],
+        Message::from_parts(&[&entity_name, what, link_name]),
+    );
+
+    Ok(())
+}
+
+/// Returns the route from the location of the acting entity to the location named by the next
+/// token.
+fn find_route_to(ctx: &mut ActionContext) -> Result<Vec<(LinkNameId, LocationId)>> {
+    let Some(location_name) = ctx.tokens.next() else {
+        ctx.printer.p(b"go to where");
+        ctx.printer.nlc(2);
+        return Err(Error::GotoWhere);
+    };
+
+    let Some(to_location_id) = ctx.world.locations.find(|x| x.name == location_name) else {
+        return Err(does_not_exist(
+            ctx.printer,
+            location_name,
+            Error::NoSuchLocation,
+        ));
+    };
+
+    let here_id = get(ctx.printer, &ctx.world.entities, ctx.entity)?.location;
+    if to_location_id == here_id {
+        ctx.printer.p(b"u r already in ");
+        ctx.printer.p(location_name);
+        ctx.printer.nlc(2);
+        return Err(Error::AlreadyThere);
+    }
+
+    let Some(route) = find_route(ctx.printer, ctx.world, here_id, to_location_id)? else {
+        ctx.printer.p(b"no route to ");
+        ctx.printer.p(location_name);
+        ctx.printer.nlc(2);
+        return Err(Error::NoRoute);
+    };
+
+    Ok(route)
+}
+
+/// Returns the exits and the locations they lead to on a route with the fewest steps from
+/// `from_id` to `to_id` or `None` if there is no route.
+///
+/// note: breadth-first search over the links, doors are not considered
+fn find_route(
+    printer: &dyn Printer,
+    world: &World,
+    from_id: LocationId,
+    to_id: LocationId,
+) -> Result<Option<Vec<(LinkNameId, LocationId)>>> {
+    let count = world.locations.slot_count();
+
+    // location and exit that a location was first reached from, by slot index
+    let mut previous: Vec<Option<(LocationId, LinkNameId)>> = Vec::new();
+    reserve(printer, &mut previous, count)?;
+    previous.resize(count, None);
+
+    // note: each location is queued at most once
+    let mut queue = Vec::new();
+    reserve(printer, &mut queue, count)?;
+    queue.push(from_id);
+
+    let mut next = 0;
+    while let Some(&location_id) = queue.get(next) {
+        next += 1;
+        if location_id == to_id {
+            break;
+        }
+        let Some(location) = world.locations.get(location_id) else {
+            continue;
+        };
+        for link in &location.links {
+            let index = link.location.index() as usize;
+            if link.location == from_id
+                || !world.locations.contains(link.location)
+                || previous[index].is_some()
+            {
+                continue;
+            }
+            previous[index] = Some((location_id, link.link_name));
+            queue.push(link.location);
+        }
+    }
+
+    // walk back from the destination
+    let mut route = Vec::new();
+    let mut location_id = to_id;
+    while location_id != from_id {
+        let Some((previous_id, link_name_id)) = previous[location_id.index() as usize] else {
+            return Ok(None);
+        };
+        reserve(printer, &mut route, 1)?;
+        route.push((link_name_id, location_id));
+        location_id = previous_id;
+    }
+    route.reverse();
+
+    Ok(Some(route))
+}
+
+/// Number of cells in a row and in a column of the map. The current location is in the middle.
+const MAP_SIZE: usize = 7;
+
+/// Width of a map cell with the location name in brackets.
+const MAP_CELL_WIDTH: usize = 9;
+
+/// Width of a map line with a space or connector between cells.
+const MAP_WIDTH: usize = MAP_SIZE * (MAP_CELL_WIDTH + 1) - 1;
+
+/// Locations by row and column.
+type MapGrid = [[Option<LocationId>; MAP_SIZE]; MAP_SIZE];
+
+/// Compass link names and the steps on the map they stand for.
+const COMPASS: [(&[u8], isize, isize); 16] = [
+    (b"north", 0, -1),
+    (b"n", 0, -1),
+    (b"northeast", 1, -1),
+    (b"ne", 1, -1),
+    (b"east", 1, 0),
+    (b"e", 1, 0),
+    (b"southeast", 1, 1),
+    (b"se", 1, 1),
+    (b"south", 0, 1),
+    (b"s", 0, 1),
+    (b"southwest", -1, 1),
+    (b"sw", -1, 1),
+    (b"west", -1, 0),
+    (b"w", -1, 0),
+    (b"northwest", -1, -1),
+    (b"nw", -1, -1),
+];
+
+/// Returns the step on the map for the link or `None` if the link name is not a compass name.
+fn map_step(world: &World, link: &Link) -> Option<(isize, isize)> {
+    let link_name = world.link_names.get(link.link_name)?;
+    COMPASS
+        .iter()
+        .find(|(name, _, _)| link_name.name == *name)
+        .map(|&(_, dx, dy)| (dx, dy))
+}
+
+/// Returns the location in the cell at a step from `x`, `y` or `None` if the cell is empty or
+/// off the map.
+fn map_cell(grid: &MapGrid, x: usize, y: usize, dx: isize, dy: isize) -> Option<LocationId> {
+    let x = x.checked_add_signed(dx)?;
+    let y = y.checked_add_signed(dy)?;
+    *grid.get(y)?.get(x)?
+}
+
+/// Lays out the locations reachable from `here_id` through compass links.
+///
+/// note: breadth-first thus a location is placed at the first coordinate it is reached at, links
+///       that disagree with the layout or lead off the map are ignored
+fn layout_map(world: &World, here_id: LocationId) -> MapGrid {
+    let mut grid: MapGrid = [[None; MAP_SIZE]; MAP_SIZE];
+    let center = MAP_SIZE / 2;
+    grid[center][center] = Some(here_id);
+
+    // note: a cell is queued once when it is filled thus the queue does not overflow
+    let mut queue = [(0, 0); MAP_SIZE * MAP_SIZE];
+    queue[0] = (center, center);
+    let mut len = 1;
+    let mut next = 0;
+    while next < len {
+        let (x, y) = queue[next];
+        next += 1;
+        let Some(location) = grid[y][x].and_then(|id| world.locations.get(id)) else {
+            continue;
+        };
+        for link in &location.links {
+            let Some((dx, dy)) = map_step(world, link) else {
+                continue;
+            };
+            let (Some(to_x), Some(to_y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+            else {
+                continue;
+            };
+            if to_x >= MAP_SIZE
+                || to_y >= MAP_SIZE
+                || grid[to_y][to_x].is_some()
+                || !world.locations.contains(link.location)
+                || grid.iter().flatten().any(|&x| x == Some(link.location))
+            {
+                continue;
+            }
+            grid[to_y][to_x] = Some(link.location);
+            queue[len] = (to_x, to_y);
+            len += 1;
+        }
+    }
+
+    grid
+}
+
+/// Returns true if the locations in the cell at `x`, `y` and the cell at a step from it are
+/// linked in that direction from either side.
+fn map_connected(world: &World, grid: &MapGrid, x: usize, y: usize, dx: isize, dy: isize) -> bool {
+    let (Some(from_id), Some(to_id)) = (grid[y][x], map_cell(grid, x, y, dx, dy)) else {
+        return false;
+    };
+    let has_link = |from_id, to_id, step| {
+        world.locations.get(from_id).is_some_and(|location| {
+            location
+                .links
+                .iter()
+                .any(|x| x.location == to_id && map_step(world, x) == Some(step))
+        })
+    };
+    has_link(from_id, to_id, (dx, dy)) || has_link(to_id, from_id, (-dx, -dy))
+}
+
+/// Prints a map line without trailing spaces.
+fn print_map_line(printer: &dyn Printer, line: &[u8]) {
+    let end = line.iter().rposition(|&x| x != b' ').map_or(0, |i| i + 1);
+    printer.pl(&line[..end]);
+}
+
+/// Object or entity that an entity can see.
+enum Thing {
+    Object(ObjectId),
+    Entity(EntityId),
+}
+
+/// Returns the object named `name` in the inventory or location of the acting entity, including
+/// objects in containers, or the entity named `name` in its location.
+fn find_thing(ctx: &ActionContext, name: &[u8]) -> Result<Thing> {
+    let entity = get(ctx.printer, &ctx.world.entities, ctx.entity)?;
+    let location = get(ctx.printer, &ctx.world.locations, entity.location)?;
+
+    if let Some((oid, _)) = find_nested_object(&ctx.world.objects, &entity.objects, name)
+        .or_else(|| find_nested_object(&ctx.world.objects, &location.objects, name))
+    {
+        return Ok(Thing::Object(oid));
+    }
+
+    if let Some(&eid) = location
+        .entities
+        .iter()
+        .find(|&&eid| ctx.world.entities.get(eid).is_some_and(|x| x.name == name))
+    {
+        return Ok(Thing::Entity(eid));
+    }
+
+    ctx.printer.p(name);
+    ctx.printer.p(b" not here");
+    ctx.printer.nlc(2);
+    Err(Error::NotHere)
+}
+
+fn find_or_add_link(world: &mut World, link_name: &[u8]) -> LinkNameId {
+    if let Some(id) = world.link_names.find(|x| x.name == link_name) {
+        return id;
+    }
+
+    world.link_names.insert(LinkName {
+        name: Name::from(link_name),
+    })
+}
+
+/// Sends `message` to the entities in `location` except `exclude_entities`.
+/// Returns false and sends nothing if out of memory.
+/// note: actions that already changed the world ignore the result thus the message is lost
+fn send_message_to_entities_in_location(
+    world: &mut World,
+    location: LocationId,
+    exclude_entities: &[EntityId],
+    message: Message,
+) -> bool {
+    let entities = world
+        .locations
+        .get(location)
+        .map_or(&[][..], |x| &x.entities);
+    let recipients = || entities.iter().filter(|x| !exclude_entities.contains(x));
+    if recipients().any(|&eid| {
+        world
+            .entities
+            .get_mut(eid)
+            .is_some_and(|x| x.messages.try_reserve(1).is_err())
+    }) {
+        return false;
+    }
+    for &eid in recipients() {
+        if let Some(x) = world.entities.get_mut(eid) {
+            x.messages.push(message);
+        }
+    }
+
+    true
+}
+
+fn print_description(printer: &dyn Printer, name: &[u8], description: &[u8]) {
+    printer.p(name);
+    printer.p(b": ");
+    printer.pl(description);
+}
+
+fn print_persistence_error(ctx: &ActionContext, error: &persistence::Error) {
+    let message: &[u8] = match error {
+        persistence::Error::CardNotReady => b"SD card not ready",
+        persistence::Error::PartitionOverlap => b"saved world would overlap a partition",
+        persistence::Error::TooLarge => b"world too large to save",
+        persistence::Error::OutOfMemory => b"out of memory",
+        persistence::Error::NoImage => b"no saved world",
+        persistence::Error::UnsupportedVersion => b"saved world has unsupported version",
+        persistence::Error::Corrupt => b"saved world is corrupt",
+    };
+    ctx.printer.p(message);
+    ctx.printer.nlc(2);
+}
+
+fn print_file_entry(printer: &dyn Printer, entry: &fat::Entry) {
+    printer.p(&entry.name);
+    if entry.is_directory() {
+        printer.pb(b'/');
+    } else {
+        printer.pb(b' ');
+        Number::u32(entry.size).print(printer);
+    }
+    printer.nl();
+}
+
+fn print_fat_error(ctx: &ActionContext, path: &[u8], error: &fat::Error) {
+    let (about_path, message): (bool, &[u8]) = match error {
+        fat::Error::CardNotReady => (false, b"SD card not ready"),
+        fat::Error::NoFileSystem => (false, b"no FAT file system on SD card"),
+        fat::Error::Unsupported => (false, b"unsupported file system"),
+        fat::Error::Corrupt => (false, b"file system is corrupt"),
+        fat::Error::NotFound => (true, b" not found"),
+        fat::Error::NotADirectory => (true, b" not a directory"),
+        fat::Error::IsADirectory => (true, b" is a directory"),
+        fat::Error::AlreadyExists => (true, b" already exists"),
+        fat::Error::InvalidName => (true, b" not a valid 8.3 name"),
+        fat::Error::DirectoryNotEmpty => (true, b" not empty"),
+        fat::Error::DirectoryFull => (false, b"directory full"),
+        fat::Error::Full => (false, b"SD card full"),
+    };
+    if about_path {
+        ctx.printer.p(path);
+    }
+    ctx.printer.p(message);
+    ctx.printer.nlc(2);
+}
+
+/// Sends `message` to `entities`.
+/// Returns false and sends nothing if out of memory.
+fn send_message_to_entities(world: &mut World, entities: &[EntityId], message: Message) -> bool {
+    if entities.iter().any(|&eid| {
+        world
+            .entities
+            .get_mut(eid)
+            .is_some_and(|x| x.messages.try_reserve(1).is_err())
+    }) {
+        return false;
+    }
+    for &eid in entities {
+        if let Some(x) = world.entities.get_mut(eid) {
+            x.messages.push(message);
+        }
+    }
+
+    true
+}
+
+/// Reserves capacity for `additional` elements in `vec` or prints "out of memory".
+fn reserve<T>(printer: &dyn Printer, vec: &mut Vec<T>, additional: usize) -> Result<()> {
+    if vec.try_reserve(additional).is_err() {
+        printer.p(b"out of memory");
+        printer.nlc(2);
+        return Err(Error::OutOfMemory);
+    }
+
+    Ok(())
+}
+
+/// Reserves slots for `additional` elements in `arena` or prints "out of memory".
+fn reserve_slots<T>(printer: &dyn Printer, arena: &mut Arena<T>, additional: usize) -> Result<()> {
+    if arena.try_reserve(additional).is_err() {
+        printer.p(b"out of memory");
+        printer.nlc(2);
+        return Err(Error::OutOfMemory);
+    }
+
+    Ok(())
+}
+
+/// Returns the element `id` refers to or prints "stale id" if it was removed.
+fn get<'a, T>(printer: &dyn Printer, arena: &'a Arena<T>, id: Id<T>) -> Result<&'a T> {
+    arena.get(id).ok_or_else(|| stale_id(printer))
+}
+
+/// Returns the element `id` refers to or prints "stale id" if it was removed.
+fn get_mut<'a, T>(printer: &dyn Printer, arena: &'a mut Arena<T>, id: Id<T>) -> Result<&'a mut T> {
+    arena.get_mut(id).ok_or_else(|| stale_id(printer))
+}
+
+/// Prints `link_name` followed by `message` and returns `error`.
+fn exit_error(printer: &dyn Printer, link_name: &[u8], message: &[u8], error: Error) -> Error {
+    printer.p(link_name);
+    printer.p(message);
+    printer.nlc(2);
+    error
+}
+
+/// Prints that `name` does not exist and returns `error`.
+fn does_not_exist(printer: &dyn Printer, name: &[u8], error: Error) -> Error {
+    printer.p(name);
+    printer.p(b" does not exist");
+    printer.nlc(2);
+    error
+}
+
+fn stale_id(printer: &dyn Printer) -> Error {
+    printer.p(b"stale id");
+    printer.nlc(2);
+    Error::StaleId
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,1396 @@
+use super::*;
+use crate::lib::api_memory::{HardwareMemory, PrinterMemory};
+use crate::lib::fat::ROOT;
+use crate::lib::fat_image::FatImage;
+use alloc::vec::Vec;
+
+type Action = fn(&mut ActionContext) -> Result<()>;
+
+// note: ids of entities and locations in the order that `Harness` creates them
+const U: EntityId = Id::new(0, 0);
+const ME: EntityId = Id::new(1, 0);
+const SU: EntityId = Id::new(2, 0);
+const ROOME: LocationId = Id::new(0, 0);
+const HALL: LocationId = Id::new(1, 0);
+
+struct Harness {
+    world: World,
+    hardware: HardwareMemory,
+    printer: PrinterMemory,
+    working_directory: Cluster,
+}
+
+impl Harness {
+    /// Entity "u" in location "roome".
+    fn new() -> Self {
+        Self {
+            world: World {
+                entities: Arena::from(vec![Entity {
+                    name: Name::from(b"u"),
+                    description: Note::default(),
+                    location: Id::new(0, 0),
+                    objects: vec![],
+                    messages: vec![],
+                    max_weight: DEFAULT_MAX_WEIGHT,
+                    max_bulk: DEFAULT_MAX_BULK,
+                }]),
+                locations: Arena::from(vec![Location {
+                    name: Name::from(b"roome"),
+                    note: Note::default(),
+                    links: vec![],
+                    objects: vec![],
+                    entities: vec![Id::new(0, 0)],
+                }]),
+                objects: Arena::new(),
+                link_names: Arena::new(),
+            },
+            hardware: HardwareMemory::new(2048),
+            printer: PrinterMemory::new(),
+            working_directory: ROOT,
+        }
+    }
+
+    /// Runs `action` for `entity` with `args` and returns the result and the output.
+    fn run(&mut self, entity: EntityId, action: Action, args: &[u8]) -> (Result<()>, Vec<u8>) {
+        let mut command_buffer = CommandBuffer::new();
+        for &byte in args {
+            assert!(command_buffer.insert(byte).is_ok());
+        }
+        let mut ctx = ActionContext {
+            printer: &mut self.printer,
+            hardware: &mut self.hardware,
+            world: &mut self.world,
+            entity,
+            tokens: &mut command_buffer.iter_tokens(u8::is_ascii_whitespace),
+            working_directory: &mut self.working_directory,
+        };
+        let result = action(&mut ctx);
+        (result, self.printer.take())
+    }
+
+    /// Runs `action` and asserts that it succeeded.
+    fn ok(&mut self, entity: EntityId, action: Action, args: &[u8]) -> Vec<u8> {
+        let (result, output) = self.run(entity, action, args);
+        assert!(
+            result.is_ok(),
+            "{}",
+            core::str::from_utf8(&output).unwrap_or("")
+        );
+        output
+    }
+
+    /// Runs `action` and returns the error and the output.
+    fn err(&mut self, entity: EntityId, action: Action, args: &[u8]) -> (Error, Vec<u8>) {
+        match self.run(entity, action, args) {
+            (Err(e), output) => (e, output),
+            (Ok(()), _) => panic!("expected action to fail"),
+        }
+    }
+
+    /// Location "roome" with exit "north" to "hall" that has exit "south" back and
+    /// a second entity "me".
+    fn with_hall() -> Self {
+        let mut h = Self::new();
+        h.ok(U, new_location, b"north south hall");
+        h.ok(U, new_entity, b"me");
+        h
+    }
+
+    /// SD card with a FAT32 partition having "README.TXT" and "DOCS/NOTES.TXT".
+    fn with_files() -> Self {
+        let mut image = FatImage::fat32();
+        image.file(ROOT, b"README.TXT", b"hello\r\nworld");
+        let docs = image.directory(ROOT, b"DOCS");
+        image.file(docs, b"NOTES.TXT", b"notes\n");
+
+        let mut h = Self::new();
+        h.hardware = image.hardware;
+        h
+    }
+
+    fn object_id(&self, name: &[u8]) -> ObjectId {
+        self.world
+            .objects
+            .find(|x| x.name == name)
+            .expect("object should exist")
+    }
+
+    fn entity(&self, id: EntityId) -> &Entity {
+        self.world.entities.get(id).expect("entity should exist")
+    }
+
+    fn location(&self, id: LocationId) -> &Location {
+        self.world.locations.get(id).expect("location should exist")
+    }
+
+    fn link_name(&self, id: LinkNameId) -> &[u8] {
+        &self
+            .world
+            .link_names
+            .get(id)
+            .expect("link name should exist")
+            .name
+    }
+
+    fn messages(&self, entity: EntityId) -> Vec<&[u8]> {
+        self.entity(entity).messages.iter().map(|x| &**x).collect()
+    }
+}
+
+#[test]
+fn look_lists_entities_objects_exits_note_and_messages() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"lamp");
+    h.ok(U, drop, b"lamp");
+    h.ok(U, set_location_note, b"dusty");
+    h.ok(ME, say, b"hi");
+
+    let output = h.ok(U, look, b"");
+    assert_eq!(
+        output,
+        b"u r in roome\nu c me, lamp\nexits: north\ndusty\nme says hi\n"
+    );
+    assert!(h.entity(U).messages.is_empty());
+}
+
+#[test]
+fn look_at_empty_location() {
+    let mut h = Harness::new();
+    let output = h.ok(U, look, b"");
+    assert_eq!(output, b"u r in roome\nu c nothing\nexits: none\n");
+}
+
+#[test]
+fn stale_entity_id_is_rejected() {
+    let mut h = Harness::with_hall();
+    assert!(h.world.entities.remove(ME).is_some());
+
+    let (e, output) = h.err(ME, say, b"hi");
+    assert!(matches!(e, Error::StaleId));
+    assert_eq!(output, b"stale id\n\n");
+
+    // note: id left in the location is skipped
+    let output = h.ok(U, look, b"");
+    assert_eq!(output, b"u r in roome\nu c nothing\nexits: north\n");
+}
+
+#[test]
+fn go_moves_entity_and_notifies() {
+    let mut h = Harness::with_hall();
+    h.ok(U, go, b"north");
+
+    assert_eq!(h.entity(U).location, HALL);
+    assert_eq!(h.location(ROOME).entities, [ME]);
+    assert_eq!(h.location(HALL).entities, [U]);
+    assert_eq!(h.messages(ME), [b"u left to north"]);
+
+    h.ok(ME, go, b"north");
+    assert_eq!(h.messages(U), [b"me arrived from south"]);
+}
+
+#[test]
+fn go_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, output) = h.err(U, go, b"");
+    assert!(matches!(e, Error::GoWhere));
+    assert_eq!(output, b"go where\n\n");
+
+    let (e, _) = h.err(U, go, b"up");
+    assert!(matches!(e, Error::NoSuchExit));
+
+    // link name exists but not from this location
+    let (e, _) = h.err(U, go, b"south");
+    assert!(matches!(e, Error::CannotGoThere));
+    assert_eq!(h.entity(U).location, ROOME);
+}
+
+#[test]
+fn go_rejects_entity_missing_from_its_location() {
+    let mut h = Harness::with_hall();
+    if let Some(x) = h.world.locations.get_mut(ROOME) {
+        x.entities.retain(|&x| x != U);
+    }
+
+    let (e, output) = h.err(U, go, b"north");
+    assert!(matches!(e, Error::StaleId));
+    assert_eq!(output, b"stale id\n\n");
+    assert_eq!(h.entity(U).location, ROOME);
+    assert!(h.location(HALL).entities.is_empty());
+}
+
+#[test]
+fn inventory_lists_objects_and_load() {
+    let mut h = Harness::new();
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: nothing\nload: 0/50 weight, 0/10 bulk\n"
+    );
+
+    h.ok(U, new_object, b"pen");
+    h.ok(U, new_object, b"cup");
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: pen, cup\nload: 2/50 weight, 2/10 bulk\n"
+    );
+}
+
+#[test]
+fn take_and_drop() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"pen");
+    let pen = h.object_id(b"pen");
+
+    h.ok(U, drop, b"pen");
+    assert!(h.entity(U).objects.is_empty());
+    assert_eq!(h.location(ROOME).objects, [pen]);
+    assert_eq!(h.messages(ME), [b"u dropped pen"]);
+
+    h.ok(ME, take, b"pen");
+    assert!(h.location(ROOME).objects.is_empty());
+    assert_eq!(h.entity(ME).objects, [pen]);
+    assert_eq!(h.messages(U), [b"me took pen"]);
+}
+
+#[test]
+fn take_errors() {
+    let mut h = Harness::new();
+
+    let (e, _) = h.err(U, take, b"");
+    assert!(matches!(e, Error::TakeWhat));
+
+    let (e, output) = h.err(U, take, b"pen");
+    assert!(matches!(e, Error::ObjectNotHere));
+    assert_eq!(output, b"pen not here\n\n");
+}
+
+#[test]
+fn drop_errors() {
+    let mut h = Harness::new();
+
+    let (e, _) = h.err(U, drop, b"");
+    assert!(matches!(e, Error::DropWhat));
+
+    let (e, output) = h.err(U, drop, b"pen");
+    assert!(matches!(e, Error::ObjectNotInInventory));
+    assert_eq!(output, b"pen not in inventory\n\n");
+}
+
+#[test]
+fn give_moves_object_and_notifies() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_entity, b"su");
+    h.ok(U, new_object, b"pen");
+    let pen = h.object_id(b"pen");
+
+    h.ok(U, give, b"me pen");
+    assert!(h.entity(U).objects.is_empty());
+    assert_eq!(h.entity(ME).objects, [pen]);
+    assert_eq!(h.messages(ME), [b"u gave u pen"]);
+    assert_eq!(h.messages(SU), [b"u gave me pen"]);
+}
+
+#[test]
+fn give_errors() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"pen");
+
+    let (e, _) = h.err(U, give, b"");
+    assert!(matches!(e, Error::GiveToWhom));
+
+    let (e, _) = h.err(U, give, b"me");
+    assert!(matches!(e, Error::GiveWhat));
+
+    let (e, _) = h.err(U, give, b"me cup");
+    assert!(matches!(e, Error::ObjectNotInInventory));
+
+    let (e, output) = h.err(U, give, b"su pen");
+    assert!(matches!(e, Error::EntityNotHere));
+    assert_eq!(output, b"su not here\n\n");
+}
+
+#[test]
+fn memory_info_prints_hardware_memory() {
+    let mut h = Harness::new();
+    let output = h.ok(U, memory_info, b"");
+    assert!(output.starts_with(b"   heap start: 0000:1000\n"));
+    let peak: &[u8] = b"\n   stack peak: 512 of 65536 bytes\n";
+    assert!(output.windows(peak.len()).any(|x| x == peak));
+    assert!(output.ends_with(
+        b"  allocations: 10\n\
+        \x20       frees: 4\n\
+        \x20 heap in use: 600 bytes, peak 800 bytes\n\
+        \x20   heap free: 1000 bytes in 3 blocks, largest 750 bytes\n\
+        fragmentation: 25%\n"
+    ));
+}
+
+#[test]
+fn memory_info_blocks_prints_block_list() {
+    let mut h = Harness::new();
+    assert_eq!(
+        h.ok(U, memory_info, b"blocks"),
+        b"heap blocks:\nheap blocks not available\n"
+    );
+    let (e, output) = h.err(U, memory_info, b"all");
+    assert!(matches!(e, Error::MemoryInfoWhat));
+    assert_eq!(output, b"mi or mi blocks\n\n");
+}
+
+#[test]
+fn heap_check_prints_ok() {
+    let mut h = Harness::new();
+    assert_eq!(h.ok(U, heap_check, b""), b"heap ok\n");
+}
+
+#[test]
+fn reserve_reports_out_of_memory() {
+    let printer = PrinterMemory::new();
+    let mut objects: Vec<Object> = Vec::new();
+    let result = reserve(&printer, &mut objects, usize::MAX);
+    assert!(matches!(result, Err(Error::OutOfMemory)));
+    assert_eq!(printer.take(), b"out of memory\n\n");
+    assert!(reserve(&printer, &mut objects, 1).is_ok());
+    assert!(objects.capacity() >= 1);
+}
+
+#[test]
+fn sdcard_status_prints_status() {
+    let mut h = Harness::new();
+    assert_eq!(h.ok(U, sdcard_status, b""), b"SDCARD_STATUS: 6\n");
+}
+
+#[test]
+fn sdcard_write_and_read() {
+    let mut h = Harness::new();
+    h.ok(U, sdcard_write, b"3 hello world");
+    assert_eq!(&h.hardware.sector(3)[..12], b"hello world\0");
+
+    let output = h.ok(U, sdcard_read, b"3");
+    assert_eq!(output.len(), SDCARD_SECTOR_SIZE_BYTES + 1);
+    assert!(output.starts_with(b"hello world\0"));
+}
+
+#[test]
+fn sdcard_errors() {
+    let mut h = Harness::new();
+
+    let (e, _) = h.err(U, sdcard_read, b"");
+    assert!(matches!(e, Error::WhatSector));
+
+    let (e, _) = h.err(U, sdcard_write, b"");
+    assert!(matches!(e, Error::WhatSector));
+}
+
+#[test]
+fn led_set_inverts_bits() {
+    let mut h = Harness::new();
+    h.ok(U, led_set, b"101");
+    assert_eq!(h.hardware.leds, !0b101);
+
+    let (e, _) = h.err(U, led_set, b"");
+    assert!(matches!(e, Error::WhichLeds));
+}
+
+#[test]
+fn new_object_into_inventory() {
+    let mut h = Harness::new();
+    h.ok(U, new_object, b"pen");
+    assert_eq!(h.entity(U).objects, [h.object_id(b"pen")]);
+
+    let (e, _) = h.err(U, new_object, b"");
+    assert!(matches!(e, Error::WhatObjectName));
+
+    let (e, _) = h.err(U, new_object, b"pen");
+    assert!(matches!(e, Error::ObjectAlreadyExists));
+    assert_eq!(h.world.objects.len(), 1);
+}
+
+#[test]
+fn new_location_links_both_ways() {
+    let mut h = Harness::with_hall();
+    let north = &h.location(ROOME).links[0];
+    assert_eq!(h.link_name(north.link_name), b"north");
+    assert_eq!(north.location, HALL);
+
+    let back = &h.location(HALL).links[0];
+    assert_eq!(h.link_name(back.link_name), b"south");
+    assert_eq!(back.location, ROOME);
+
+    // link names are shared
+    h.ok(U, new_location, b"south north cellar");
+    assert_eq!(h.world.link_names.len(), 2);
+}
+
+#[test]
+fn new_location_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, new_location, b"");
+    assert!(matches!(e, Error::WhatToLinkName));
+
+    let (e, _) = h.err(U, new_location, b"east");
+    assert!(matches!(e, Error::WhatBackLinkName));
+
+    let (e, _) = h.err(U, new_location, b"east west");
+    assert!(matches!(e, Error::WhatNewLocationName));
+
+    let (e, _) = h.err(U, new_location, b"east west hall");
+    assert!(matches!(e, Error::LocationAlreadyExists));
+
+    let (e, _) = h.err(U, new_location, b"north south attic");
+    assert!(matches!(e, Error::LinkFromLocationAlreadyExists));
+
+    assert_eq!(h.world.locations.len(), 2);
+}
+
+#[test]
+fn new_entity_in_current_location() {
+    let mut h = Harness::with_hall();
+    h.ok(U, go, b"north");
+    h.ok(U, new_entity, b"su");
+    assert_eq!(h.entity(SU).location, HALL);
+    assert_eq!(h.location(HALL).entities, [U, SU]);
+
+    let (e, _) = h.err(U, new_entity, b"");
+    assert!(matches!(e, Error::WhatEntityName));
+
+    let (e, _) = h.err(U, new_entity, b"me");
+    assert!(matches!(e, Error::EntityAlreadyExists));
+}
+
+#[test]
+fn remove_object_from_inventory_and_location() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"pen");
+    h.ok(U, new_object, b"cup");
+    h.ok(U, drop, b"cup");
+    let pen = h.object_id(b"pen");
+
+    h.ok(U, remove_object, b"pen");
+    h.ok(U, remove_object, b"cup");
+    assert!(h.entity(U).objects.is_empty());
+    assert!(h.location(ROOME).objects.is_empty());
+    assert!(h.world.objects.is_empty());
+    assert!(!h.world.objects.contains(pen));
+
+    let (e, _) = h.err(U, remove_object, b"");
+    assert!(matches!(e, Error::RemoveObjectWhat));
+
+    let (e, output) = h.err(U, remove_object, b"pen");
+    assert!(matches!(e, Error::NoSuchObject));
+    assert_eq!(output, b"pen does not exist\n\n");
+}
+
+#[test]
+fn remove_location_moves_entities_here() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_object, b"hat");
+    h.ok(ME, drop, b"hat");
+    h.ok(ME, new_location, b"up down attic");
+    h.ok(U, new_entity, b"su");
+    let told = h.messages(U).len();
+
+    h.ok(U, remove_location, b"hall");
+    assert!(!h.world.locations.contains(HALL));
+    assert_eq!(h.entity(ME).location, ROOME);
+    assert_eq!(h.location(ROOME).entities, [U, SU, ME]);
+    assert_eq!(h.messages(SU), [b"me arrived"]);
+    assert_eq!(
+        h.messages(ME).last(),
+        Some(&b"hall vanished, u r in roome".as_slice())
+    );
+    assert_eq!(h.messages(U).len(), told);
+    assert!(h.location(ROOME).links.is_empty());
+    assert!(h.world.objects.is_empty());
+
+    let attic = h
+        .world
+        .locations
+        .find(|x| &*x.name == b"attic")
+        .expect("attic should exist");
+    assert!(h.location(attic).links.is_empty());
+}
+
+#[test]
+fn remove_location_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, remove_location, b"");
+    assert!(matches!(e, Error::RemoveLocationWhat));
+
+    let (e, output) = h.err(U, remove_location, b"cellar");
+    assert!(matches!(e, Error::NoSuchLocation));
+    assert_eq!(output, b"cellar does not exist\n\n");
+
+    let (e, output) = h.err(U, remove_location, b"roome");
+    assert!(matches!(e, Error::CannotRemoveCurrentLocation));
+    assert_eq!(output, b"cannot remove location u r in\n\n");
+    assert_eq!(h.world.locations.len(), 2);
+}
+
+#[test]
+fn remove_entity_and_its_inventory() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, new_object, b"hat");
+
+    h.ok(U, remove_entity, b"me");
+    assert!(!h.world.entities.contains(ME));
+    assert!(h.world.objects.is_empty());
+    assert_eq!(h.location(ROOME).entities, [U]);
+
+    // note: new entity reuses the slot thus the removed id stays stale
+    h.ok(U, new_entity, b"su");
+    let su = h.location(ROOME).entities[1];
+    assert_eq!(su.index(), ME.index());
+    assert!(!h.world.entities.contains(ME));
+
+    let (e, _) = h.err(U, remove_entity, b"");
+    assert!(matches!(e, Error::RemoveEntityWhat));
+
+    let (e, output) = h.err(U, remove_entity, b"me");
+    assert!(matches!(e, Error::NoSuchEntity));
+    assert_eq!(output, b"me does not exist\n\n");
+
+    let (e, output) = h.err(U, remove_entity, b"u");
+    assert!(matches!(e, Error::CannotRemoveSelf));
+    assert_eq!(output, b"cannot remove urself\n\n");
+}
+
+#[test]
+fn unlink_removes_both_directions() {
+    let mut h = Harness::with_hall();
+    h.ok(U, unlink, b"north");
+    assert!(h.location(ROOME).links.is_empty());
+    assert!(h.location(HALL).links.is_empty());
+
+    let (e, _) = h.err(U, unlink, b"");
+    assert!(matches!(e, Error::UnlinkWhat));
+
+    let (e, output) = h.err(U, unlink, b"north");
+    assert!(matches!(e, Error::NoSuchExit));
+    assert_eq!(output, b"north is not an exit\n\n");
+}
+
+#[test]
+fn unlink_keeps_other_exits_between_locations() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_link, b"stairs hall");
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_link, b"chute roome");
+
+    h.ok(U, unlink, b"north");
+    let names = |h: &Harness, id| -> Vec<Vec<u8>> {
+        h.location(id)
+            .links
+            .iter()
+            .filter_map(|x| h.world.link_names.get(x.link_name))
+            .map(|x| x.name.to_vec())
+            .collect()
+    };
+    assert_eq!(names(&h, ROOME), [b"stairs".to_vec()]);
+    assert_eq!(names(&h, HALL), [b"chute".to_vec()]);
+}
+
+#[test]
+fn unlink_removes_reverse_not_first_link_back() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, unlink, b"south");
+    h.ok(ME, new_link, b"chute roome");
+    h.ok(ME, new_link, b"south roome");
+    h.ok(U, new_link, b"north hall");
+
+    // note: chute leads back first but south is the reverse of north
+    h.ok(U, go, b"north");
+    assert_eq!(h.messages(ME), [b"u arrived from south"]);
+    h.ok(U, go, b"south");
+
+    h.ok(U, unlink, b"north");
+    assert!(h.location(ROOME).links.is_empty());
+    assert_eq!(h.location(HALL).links.len(), 1);
+    let output = h.ok(ME, look, b"");
+    assert!(output.starts_with(b"u r in hall\nu c nothing\nexits: chute\n"));
+}
+
+#[test]
+fn rename_keeps_names_unique() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"pen");
+    h.ok(U, new_object, b"cup");
+
+    h.ok(U, rename, b"object pen quill");
+    assert_eq!(h.world.objects.find(|x| &*x.name == b"pen"), None);
+    let quill = h.object_id(b"quill");
+    assert!(h.entity(U).objects.contains(&quill));
+    h.ok(U, rename, b"location hall cellar");
+    assert_eq!(&*h.location(HALL).name, b"cellar");
+    h.ok(U, rename, b"entity me su");
+    assert_eq!(&*h.entity(ME).name, b"su");
+
+    let (e, output) = h.err(U, rename, b"object cup quill");
+    assert!(matches!(e, Error::ObjectAlreadyExists));
+    assert_eq!(output, b"object already exists\n\n");
+
+    let (e, _) = h.err(U, rename, b"location roome cellar");
+    assert!(matches!(e, Error::LocationAlreadyExists));
+
+    let (e, _) = h.err(U, rename, b"entity su u");
+    assert!(matches!(e, Error::EntityAlreadyExists));
+}
+
+#[test]
+fn rename_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, output) = h.err(U, rename, b"exit north up");
+    assert!(matches!(e, Error::RenameWhat));
+    assert_eq!(output, b"rename object, location or entity\n\n");
+
+    let (e, output) = h.err(U, rename, b"object");
+    assert!(matches!(e, Error::RenameWhat));
+    assert_eq!(output, b"rename what object\n\n");
+
+    let (e, _) = h.err(U, rename, b"entity me");
+    assert!(matches!(e, Error::WhatNewName));
+
+    let (e, output) = h.err(U, rename, b"location attic loft");
+    assert!(matches!(e, Error::NoSuchLocation));
+    assert_eq!(output, b"attic does not exist\n\n");
+}
+
+#[test]
+fn examine_shows_description() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"lamp");
+
+    let output = h.ok(U, examine, b"lamp");
+    assert_eq!(output, b"u c nothing special about lamp\n");
+
+    h.ok(U, describe, b"lamp an old brass lamp");
+    h.ok(U, describe, b"me a stranger");
+    assert_eq!(h.ok(U, examine, b"lamp"), b"an old brass lamp\n");
+    assert_eq!(h.ok(U, examine, b"me"), b"a stranger\n");
+
+    // note: description of objects in inventory are not shown by look
+    assert_eq!(
+        h.ok(U, look, b""),
+        b"u r in roome\nu c me\nexits: north\nme: a stranger\n"
+    );
+    h.ok(U, drop, b"lamp");
+    assert_eq!(
+        h.ok(U, look, b""),
+        b"u r in roome\nu c me, lamp\nexits: north\nme: a stranger\nlamp: an old brass lamp\n"
+    );
+
+    h.ok(U, describe, b"lamp");
+    assert!(h.world.objects.values().all(|x| x.description.is_empty()));
+}
+
+#[test]
+fn examine_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, examine, b"");
+    assert!(matches!(e, Error::ExamineWhat));
+
+    let (e, output) = h.err(U, examine, b"ghost");
+    assert!(matches!(e, Error::NotHere));
+    assert_eq!(output, b"ghost not here\n\n");
+
+    let (e, _) = h.err(U, describe, b"");
+    assert!(matches!(e, Error::DescribeWhat));
+
+    // note: only what is here can be described
+    h.ok(ME, go, b"north");
+    let (e, _) = h.err(U, describe, b"me a stranger");
+    assert!(matches!(e, Error::NotHere));
+}
+
+#[test]
+fn one_way_link_arrives_without_back_link() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_location, b"up down attic");
+    h.ok(ME, go, b"up");
+    h.ok(U, new_link, b"chute attic");
+
+    h.ok(U, go, b"chute");
+    assert_eq!(
+        h.entity(ME).messages.last().map(|x| &**x),
+        Some(b"u arrived".as_slice())
+    );
+
+    let output = h.ok(U, look, b"");
+    assert!(output.starts_with(b"u r in attic\nu c me\nexits: down\n"));
+}
+
+#[test]
+fn new_link_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, new_link, b"chute");
+    assert!(matches!(e, Error::LinkWhat));
+
+    let (e, output) = h.err(U, new_link, b"chute cellar");
+    assert!(matches!(e, Error::NoSuchLocation));
+    assert_eq!(output, b"cellar does not exist\n\n");
+
+    let (e, _) = h.err(U, new_link, b"north hall");
+    assert!(matches!(e, Error::LinkFromLocationAlreadyExists));
+    assert_eq!(h.location(ROOME).links.len(), 1);
+}
+
+#[test]
+fn locked_door_needs_key() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"key");
+    h.ok(U, new_door, b"north key");
+    let state = |h: &Harness, id| h.location(id).links[0].door.map(|x| x.state);
+    assert_eq!(state(&h, ROOME), Some(DoorState::Locked));
+    assert_eq!(state(&h, HALL), Some(DoorState::Locked));
+    assert!(
+        h.ok(U, look, b"")
+            .starts_with(b"u r in roome\nu c me\nexits: north (locked)\n")
+    );
+
+    let (e, output) = h.err(U, go, b"north");
+    assert!(matches!(e, Error::DoorLocked));
+    assert_eq!(output, b"north is locked\n\n");
+    let (e, _) = h.err(U, open, b"north");
+    assert!(matches!(e, Error::DoorLocked));
+
+    let (e, output) = h.err(ME, unlock, b"north");
+    assert!(matches!(e, Error::NoKey));
+    assert_eq!(output, b"u do not have the key\n\n");
+
+    h.ok(U, unlock, b"north");
+    assert_eq!(state(&h, HALL), Some(DoorState::Closed));
+    let (e, output) = h.err(U, go, b"north");
+    assert!(matches!(e, Error::DoorClosed));
+    assert_eq!(output, b"north is closed\n\n");
+
+    h.ok(U, open, b"north");
+    assert_eq!(state(&h, HALL), Some(DoorState::Open));
+    assert_eq!(
+        h.entity(ME).messages.last().map(|x| &**x),
+        Some(b"u opened north".as_slice())
+    );
+    h.ok(U, go, b"north");
+
+    h.ok(U, close, b"south");
+    h.ok(U, lock, b"south");
+    assert_eq!(state(&h, ROOME), Some(DoorState::Locked));
+}
+
+#[test]
+fn key_in_nested_container_unlocks() {
+    let mut h = Harness::with_hall();
+    for name in [b"bag".as_slice(), b"box", b"key"] {
+        h.ok(U, new_object, name);
+    }
+    h.ok(U, new_container, b"bag");
+    h.ok(U, new_container, b"box");
+    h.ok(U, new_door, b"north key");
+    h.ok(U, put, b"key in box");
+    h.ok(U, put, b"box in bag");
+
+    h.ok(U, unlock, b"north");
+    h.ok(U, lock, b"north");
+    let door = h.location(ROOME).links[0].door.map(|x| x.state);
+    assert_eq!(door, Some(DoorState::Locked));
+
+    h.ok(U, drop, b"bag");
+    let (e, _) = h.err(U, unlock, b"north");
+    assert!(matches!(e, Error::NoKey));
+}
+
+#[test]
+fn removing_key_leaves_door_closed_without_key() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"key");
+    h.ok(U, new_door, b"north key");
+    h.ok(U, remove_object, b"key");
+
+    for id in [ROOME, HALL] {
+        let door = h.location(id).links[0].door.expect("door should be kept");
+        assert_eq!(door.state, DoorState::Closed);
+        assert!(door.key.is_none());
+    }
+    h.ok(U, open, b"north");
+    h.ok(U, go, b"north");
+    h.ok(ME, go, b"north");
+
+    // note: a key carried by a removed entity is removed with it
+    h.ok(U, new_object, b"bolt");
+    h.ok(U, new_door, b"south bolt");
+    h.ok(U, give, b"me bolt");
+    h.ok(U, remove_entity, b"me");
+    assert_eq!(
+        h.location(HALL).links[0].door.map(|x| x.state),
+        Some(DoorState::Closed)
+    );
+}
+
+#[test]
+fn door_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, output) = h.err(U, open, b"north");
+    assert!(matches!(e, Error::NotADoor));
+    assert_eq!(output, b"north is not a door\n\n");
+
+    let (e, output) = h.err(U, open, b"west");
+    assert!(matches!(e, Error::NoSuchExit));
+    assert_eq!(output, b"west is not an exit\n\n");
+
+    let (e, _) = h.err(U, new_door, b"");
+    assert!(matches!(e, Error::DoorWhat));
+    let (e, _) = h.err(U, new_door, b"north key");
+    assert!(matches!(e, Error::NoSuchObject));
+
+    h.ok(U, new_door, b"north");
+    let (e, _) = h.err(U, lock, b"north");
+    assert!(matches!(e, Error::NoLock));
+    let (e, _) = h.err(U, unlock, b"north");
+    assert!(matches!(e, Error::NotLocked));
+    let (e, _) = h.err(U, close, b"north");
+    assert!(matches!(e, Error::AlreadyClosed));
+    h.ok(U, open, b"north");
+    let (e, _) = h.err(U, open, b"north");
+    assert!(matches!(e, Error::AlreadyOpen));
+
+    h.ok(U, new_object, b"key");
+    h.ok(U, new_door, b"north key");
+    h.ok(U, unlock, b"north");
+    h.ok(U, open, b"north");
+    let (e, _) = h.err(U, lock, b"north");
+    assert!(matches!(e, Error::DoorOpen));
+    h.ok(U, close, b"north");
+    h.ok(U, lock, b"north");
+    let (e, _) = h.err(U, lock, b"north");
+    assert!(matches!(e, Error::AlreadyLocked));
+
+    let (e, _) = h.err(U, open, b"");
+    assert!(matches!(e, Error::OpenWhat));
+    let (e, _) = h.err(U, close, b"");
+    assert!(matches!(e, Error::CloseWhat));
+    let (e, _) = h.err(U, lock, b"");
+    assert!(matches!(e, Error::LockWhat));
+    let (e, _) = h.err(U, unlock, b"");
+    assert!(matches!(e, Error::UnlockWhat));
+}
+
+#[test]
+fn put_in_and_take_from_container() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"bag");
+    h.ok(U, new_object, b"pen");
+    h.ok(U, new_container, b"bag");
+    let (bag, pen) = (h.object_id(b"bag"), h.object_id(b"pen"));
+
+    h.ok(U, put, b"pen in bag");
+    assert_eq!(h.entity(U).objects, [bag]);
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: bag (pen)\nload: 2/50 weight, 1/10 bulk\n"
+    );
+    assert_eq!(
+        h.ok(U, examine, b"bag"),
+        b"u c nothing special about bag\nin it: pen\n"
+    );
+
+    // note: contents move with the container
+    h.ok(U, drop, b"bag");
+    assert!(h.entity(U).objects.is_empty());
+    assert!(
+        h.ok(U, look, b"")
+            .starts_with(b"u r in roome\nu c me, bag (pen)\n")
+    );
+
+    h.ok(U, take, b"pen from bag");
+    assert_eq!(h.entity(U).objects, [pen]);
+    assert_eq!(
+        h.ok(U, examine, b"bag"),
+        b"u c nothing special about bag\nin it: nothing\n"
+    );
+
+    h.ok(U, put, b"pen in bag");
+    h.ok(U, take, b"pen");
+    assert_eq!(h.entity(U).objects, [pen]);
+    assert_eq!(h.location(ROOME).objects, [bag]);
+}
+
+#[test]
+fn containers_nest() {
+    let mut h = Harness::with_hall();
+    for name in [b"bag".as_slice(), b"box", b"pen"] {
+        h.ok(U, new_object, name);
+    }
+    h.ok(U, new_container, b"bag");
+    h.ok(U, new_container, b"box");
+    let (bag, pen) = (h.object_id(b"bag"), h.object_id(b"pen"));
+
+    h.ok(U, put, b"pen in box");
+    h.ok(U, put, b"box in bag");
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: bag (box (pen))\nload: 3/50 weight, 1/10 bulk\n"
+    );
+    assert_eq!(h.ok(U, examine, b"pen"), b"u c nothing special about pen\n");
+
+    let (e, output) = h.err(U, put, b"bag in bag");
+    assert!(matches!(e, Error::ContainerCycle));
+    assert_eq!(output, b"cannot put bag in itself\n\n");
+    let (e, _) = h.err(U, put, b"bag in box");
+    assert!(matches!(e, Error::ContainerCycle));
+
+    // note: nested objects are found in the inventory
+    h.ok(U, give, b"me pen");
+    assert_eq!(h.entity(ME).objects, [pen]);
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: bag (box)\nload: 2/50 weight, 1/10 bulk\n"
+    );
+
+    h.ok(U, remove_object, b"bag");
+    assert!(!h.world.objects.contains(bag));
+    assert_eq!(h.world.objects.len(), 1);
+}
+
+#[test]
+fn container_errors() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"bag");
+    h.ok(U, new_object, b"pen");
+    h.ok(U, new_container, b"bag");
+
+    let (e, _) = h.err(U, put, b"");
+    assert!(matches!(e, Error::PutWhat));
+    let (e, _) = h.err(U, put, b"pen");
+    assert!(matches!(e, Error::PutInWhat));
+    let (e, _) = h.err(U, put, b"pen into bag");
+    assert!(matches!(e, Error::PutInWhat));
+    let (e, output) = h.err(U, put, b"pen in ghost");
+    assert!(matches!(e, Error::ObjectNotHere));
+    assert_eq!(output, b"ghost not here\n\n");
+    let (e, output) = h.err(U, put, b"bag in pen");
+    assert!(matches!(e, Error::NotAContainer));
+    assert_eq!(output, b"pen is not a container\n\n");
+
+    let (e, _) = h.err(U, take, b"pen from");
+    assert!(matches!(e, Error::TakeFromWhat));
+    let (e, output) = h.err(U, take, b"ghost from bag");
+    assert!(matches!(e, Error::ObjectNotInContainer));
+    assert_eq!(output, b"ghost not in bag\n\n");
+
+    let (e, _) = h.err(U, new_container, b"");
+    assert!(matches!(e, Error::ContainerWhat));
+    let (e, _) = h.err(U, new_container, b"ghost");
+    assert!(matches!(e, Error::NoSuchObject));
+}
+
+#[test]
+fn take_and_give_check_load() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"anvil");
+    h.ok(U, new_object, b"pen");
+    h.ok(U, set_object, b"anvil 60 1");
+    h.ok(U, drop, b"anvil");
+    h.ok(U, drop, b"pen");
+
+    let (e, output) = h.err(U, take, b"anvil");
+    assert!(matches!(e, Error::TooHeavy));
+    assert_eq!(output, b"too heavy\n\n");
+
+    h.ok(U, set_entity, b"u 100 1");
+    h.ok(U, take, b"anvil");
+    let (e, output) = h.err(U, take, b"pen");
+    assert!(matches!(e, Error::HandsFull));
+    assert_eq!(output, b"hands full\n\n");
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: anvil\nload: 60/100 weight, 1/1 bulk\n"
+    );
+
+    let (e, output) = h.err(U, give, b"me anvil");
+    assert!(matches!(e, Error::TooHeavy));
+    assert_eq!(output, b"too heavy for me\n\n");
+    assert!(h.entity(ME).objects.is_empty());
+}
+
+#[test]
+fn container_capacity_and_weight() {
+    let mut h = Harness::with_hall();
+    for name in [b"bag".as_slice(), b"pen", b"cup"] {
+        h.ok(U, new_object, name);
+    }
+    h.ok(U, new_container, b"bag");
+    h.ok(U, set_object, b"bag 1 1 1");
+
+    h.ok(U, put, b"pen in bag");
+    let (e, output) = h.err(U, put, b"cup in bag");
+    assert!(matches!(e, Error::ContainerFull));
+    assert_eq!(output, b"bag is full\n\n");
+
+    // note: taking from a container in the inventory adds no weight
+    h.ok(U, set_entity, b"u 3 10");
+    h.ok(U, take, b"pen from bag");
+    assert_eq!(
+        h.ok(U, inventory, b""),
+        b"u have: bag, cup, pen\nload: 3/3 weight, 3/10 bulk\n"
+    );
+}
+
+#[test]
+fn set_object_and_entity_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, set_object, b"pen 1");
+    assert!(matches!(e, Error::SetObjectWhat));
+    let (e, _) = h.err(U, set_object, b"pen 1 1");
+    assert!(matches!(e, Error::NoSuchObject));
+
+    let (e, _) = h.err(U, set_entity, b"me 1");
+    assert!(matches!(e, Error::SetEntityWhat));
+    let (e, output) = h.err(U, set_entity, b"su 1 1");
+    assert!(matches!(e, Error::NoSuchEntity));
+    assert_eq!(output, b"su does not exist\n\n");
+}
+
+#[test]
+fn set_object_and_entity_refuse_what_is_not_a_number() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_object, b"lamp");
+    let lamp = h.object_id(b"lamp");
+
+    let (e, output) = h.err(U, set_object, b"lamp abc 1");
+    assert!(matches!(e, Error::NotANumber));
+    assert_eq!(output, b"abc is not a number\n\n");
+    let (e, _) = h.err(U, set_object, b"lamp 2 2 4294967296");
+    assert!(matches!(e, Error::NotANumber));
+    let object = h.world.objects.get(lamp).expect("lamp should exist");
+    assert_eq!((object.weight, object.bulk), (DEFAULT_WEIGHT, DEFAULT_BULK));
+
+    let (e, output) = h.err(U, set_entity, b"u 9x 1");
+    assert!(matches!(e, Error::NotANumber));
+    assert_eq!(output, b"9x is not a number\n\n");
+    let (e, _) = h.err(U, set_entity, b"u 1 99999999999");
+    assert!(matches!(e, Error::NotANumber));
+    assert_eq!(h.entity(U).max_weight, DEFAULT_MAX_WEIGHT);
+}
+
+#[test]
+fn goto_walks_shortest_route() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_location, b"up down attic");
+    h.ok(ME, go, b"up");
+    h.ok(ME, new_link, b"chute roome");
+    h.ok(ME, go, b"down");
+    h.ok(ME, go, b"south");
+
+    let output = h.ok(U, route, b"attic");
+    assert_eq!(output, b"north to hall, up to attic\n");
+    assert_eq!(h.entity(U).location, ROOME);
+
+    h.ok(U, goto, b"attic");
+    assert_eq!(h.entity(U).location, Id::new(2, 0));
+    assert_eq!(h.messages(ME), [b"u left to north".as_slice()]);
+    assert_eq!(h.location(ROOME).entities, [ME]);
+
+    // note: the one-way chute is shorter than down and south
+    let output = h.ok(U, route, b"roome");
+    assert_eq!(output, b"chute to roome\n");
+}
+
+#[test]
+fn goto_stops_at_locked_door() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_location, b"up down attic");
+    h.ok(ME, new_object, b"key");
+    h.ok(ME, new_door, b"up key");
+
+    let output = h.ok(U, route, b"attic");
+    assert_eq!(output, b"north to hall, up to attic (locked)\n");
+
+    // note: the first step was taken thus the walk is done
+    let output = h.ok(U, goto, b"attic");
+    assert_eq!(output, b"up is locked\n\nu stopped in hall\n");
+    assert_eq!(h.entity(U).location, HALL);
+
+    h.ok(U, go, b"south");
+    h.ok(ME, new_door, b"south key");
+    let (e, output) = h.err(U, goto, b"attic");
+    assert!(matches!(e, Error::DoorLocked));
+    assert_eq!(output, b"north is locked\n\n");
+    assert_eq!(h.entity(U).location, ROOME);
+}
+
+#[test]
+fn goto_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, goto, b"");
+    assert!(matches!(e, Error::GotoWhere));
+
+    let (e, output) = h.err(U, route, b"cellar");
+    assert!(matches!(e, Error::NoSuchLocation));
+    assert_eq!(output, b"cellar does not exist\n\n");
+
+    let (e, output) = h.err(U, goto, b"roome");
+    assert!(matches!(e, Error::AlreadyThere));
+    assert_eq!(output, b"u r already in roome\n\n");
+
+    // note: one-way link out of the cellar only
+    h.ok(U, new_location, b"down up cellar");
+    h.ok(U, go, b"down");
+    h.ok(U, unlink, b"up");
+    h.ok(U, new_link, b"up roome");
+    h.ok(U, go, b"up");
+    let (e, output) = h.err(U, route, b"cellar");
+    assert!(matches!(e, Error::NoRoute));
+    assert_eq!(output, b"no route to cellar\n\n");
+}
+
+#[test]
+fn map_lays_out_compass_links() {
+    let mut h = Harness::with_hall();
+    h.ok(ME, go, b"north");
+    h.ok(ME, new_location, b"east west kitchen");
+    h.ok(ME, new_location, b"up down attic");
+    h.ok(U, new_location, b"west east cloakroom");
+
+    let output = h.ok(U, map, b"");
+    assert_eq!(
+        output,
+        b"          [hall]----[kitchen]\n           |\n[cloakro]-<roome>\n\
+          other exits:\nhall: up to attic\n"
+    );
+}
+
+#[test]
+fn map_lists_links_that_do_not_fit() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_link, b"east hall");
+    for name in [b"w1", b"w2", b"w3", b"w4"] {
+        h.ok(ME, new_location, &[b"west east ".as_slice(), name].concat());
+        h.ok(ME, go, b"west");
+    }
+
+    // note: east to hall disagrees with north to hall and w4 is off the map
+    let output = h.ok(U, map, b"");
+    assert_eq!(
+        output,
+        b"                              [hall]\n                               |\n\
+          [w3]------[w2]------[w1]------<roome>\n\
+          other exits:\nw3: west to w4\nroome: east to hall\n"
+    );
+}
+
+#[test]
+fn export_prints_dot_or_json() {
+    let mut h = Harness::with_hall();
+    let output = h.ok(U, export, b"dot");
+    assert!(output.starts_with(b"digraph world {\n  l0 [shape=box label=\"roome\"];\n"));
+    let output = h.ok(U, export, b"json");
+    assert!(output.starts_with(b"{\n\"locations\":[\n{\"id\":0,\"name\":\"roome\""));
+
+    let (e, output) = h.err(U, export, b"xml");
+    assert!(matches!(e, Error::ExportWhat));
+    assert_eq!(output, b"export dot or json\n\n");
+}
+
+#[test]
+fn set_location_note_uses_rest_of_line() {
+    let mut h = Harness::new();
+    h.ok(U, set_location_note, b"find  an exit");
+    assert_eq!(&*h.location(ROOME).note, b"find  an exit");
+}
+
+#[test]
+fn say_to_others_in_location() {
+    let mut h = Harness::with_hall();
+    h.ok(U, say, b"hello world");
+    assert!(h.messages(U).is_empty());
+    assert_eq!(h.messages(ME), [b"u says hello world"]);
+
+    let (e, _) = h.err(U, say, b"");
+    assert!(matches!(e, Error::SayWhat));
+}
+
+#[test]
+fn tell_to_entity_in_location() {
+    let mut h = Harness::with_hall();
+    h.ok(U, new_entity, b"su");
+    h.ok(U, tell, b"me psst");
+    assert_eq!(h.messages(ME), [b"u tells u psst"]);
+    assert!(h.messages(SU).is_empty());
+}
+
+#[test]
+fn tell_errors() {
+    let mut h = Harness::with_hall();
+
+    let (e, _) = h.err(U, tell, b"");
+    assert!(matches!(e, Error::TellToWhom));
+
+    let (e, _) = h.err(U, tell, b"me");
+    assert!(matches!(e, Error::TellWhat));
+
+    let (e, _) = h.err(U, tell, b"su hi");
+    assert!(matches!(e, Error::EntityNotHere));
+}
+
+#[test]
+fn save_and_load_restore_world() {
+    let mut h = Harness::with_hall();
+    assert_eq!(h.ok(U, save, b""), b"world saved\n");
+
+    h.ok(U, new_object, b"hat");
+    h.ok(U, go, b"north");
+
+    assert_eq!(h.ok(U, load, b""), b"world loaded\n");
+    assert!(h.world.objects.is_empty());
+    assert_eq!(h.entity(U).location, ROOME);
+}
+
+#[test]
+fn save_and_load_errors() {
+    let mut h = Harness::new();
+
+    let (e, output) = h.err(U, load, b"");
+    assert!(matches!(e, Error::LoadFailed));
+    assert_eq!(output, b"no saved world\n\n");
+
+    h.hardware.sdcard_status = 0;
+
+    let (e, output) = h.err(U, save, b"");
+    assert!(matches!(e, Error::SaveFailed));
+    assert_eq!(output, b"SD card not ready\n\n");
+
+    let (e, _) = h.err(U, load, b"");
+    assert!(matches!(e, Error::LoadFailed));
+}
+
+#[test]
+fn list_directories_and_files() {
+    let mut h = Harness::with_files();
+    assert_eq!(h.ok(U, list, b""), b"README.TXT 12\nDOCS/\n");
+    assert_eq!(h.ok(U, list, b"docs"), b"NOTES.TXT 6\n");
+    assert_eq!(h.ok(U, list, b"readme.txt"), b"README.TXT 12\n");
+}
+
+#[test]
+fn cat_prints_file() {
+    let mut h = Harness::with_files();
+    assert_eq!(h.ok(U, cat, b"readme.txt"), b"hello\nworld\n");
+    assert_eq!(h.ok(U, cat, b"/docs/notes.txt"), b"notes\n");
+}
+
+#[test]
+fn change_directory_is_used_by_list_and_cat() {
+    let mut h = Harness::with_files();
+    assert!(h.ok(U, change_directory, b"docs").is_empty());
+    assert_eq!(h.ok(U, cat, b"notes.txt"), b"notes\n");
+    assert_eq!(h.ok(U, list, b".."), b"README.TXT 12\nDOCS/\n");
+
+    h.ok(U, change_directory, b"");
+    assert_eq!(h.working_directory, ROOT);
+    assert_eq!(h.ok(U, list, b""), b"README.TXT 12\nDOCS/\n");
+}
+
+#[test]
+fn file_system_errors() {
+    let mut h = Harness::with_files();
+
+    let (e, output) = h.err(U, cat, b"");
+    assert!(matches!(e, Error::CatWhat));
+    assert_eq!(output, b"cat what\n\n");
+
+    let (e, output) = h.err(U, cat, b"docs");
+    assert!(matches!(e, Error::CatFailed));
+    assert_eq!(output, b"docs is a directory\n\n");
+
+    let (e, output) = h.err(U, list, b"missing");
+    assert!(matches!(e, Error::ListFailed));
+    assert_eq!(output, b"missing not found\n\n");
+
+    let (e, output) = h.err(U, change_directory, b"readme.txt");
+    assert!(matches!(e, Error::ChangeDirectoryFailed));
+    assert_eq!(output, b"readme.txt not a directory\n\n");
+    assert_eq!(h.working_directory, ROOT);
+
+    let mut h = Harness::new();
+    let (e, output) = h.err(U, list, b"");
+    assert!(matches!(e, Error::ListFailed));
+    assert_eq!(output, b"no FAT file system on SD card\n\n");
+}
+
+#[test]
+fn write_append_and_remove_files() {
+    let mut h = Harness::with_files();
+    assert!(h.ok(U, write_file, b"log.txt first line").is_empty());
+    h.ok(U, append_file, b"log.txt second");
+    assert_eq!(h.ok(U, cat, b"log.txt"), b"first line\nsecond\n");
+
+    h.ok(U, write_file, b"log.txt");
+    assert_eq!(h.ok(U, list, b"log.txt"), b"LOG.TXT 0\n");
+
+    h.ok(U, make_directory, b"logs");
+    h.ok(U, change_directory, b"logs");
+    h.ok(U, append_file, b"today.txt hello");
+    assert_eq!(h.ok(U, list, b"/logs"), b"TODAY.TXT 6\n");
+
+    h.ok(U, remove_file, b"today.txt");
+    h.ok(U, change_directory, b"..");
+    h.ok(U, remove_file, b"logs");
+    h.ok(U, remove_file, b"log.txt");
+    assert_eq!(h.ok(U, list, b""), b"README.TXT 12\nDOCS/\n");
+}
+
+#[test]
+fn write_append_and_remove_errors() {
+    let mut h = Harness::with_files();
+
+    let (e, output) = h.err(U, write_file, b"");
+    assert!(matches!(e, Error::WriteToWhat));
+    assert_eq!(output, b"write to what\n\n");
+
+    let (e, output) = h.err(U, write_file, b"docs hello");
+    assert!(matches!(e, Error::WriteFailed));
+    assert_eq!(output, b"docs is a directory\n\n");
+
+    let (e, _) = h.err(U, append_file, b"");
+    assert!(matches!(e, Error::AppendToWhat));
+
+    let (e, output) = h.err(U, append_file, b"log.txt");
+    assert!(matches!(e, Error::AppendWhat));
+    assert_eq!(output, b"append what\n\n");
+
+    let (e, output) = h.err(U, append_file, b"long_name.txt x");
+    assert!(matches!(e, Error::AppendFailed));
+    assert_eq!(output, b"long_name.txt not a valid 8.3 name\n\n");
+
+    let (e, _) = h.err(U, remove_file, b"");
+    assert!(matches!(e, Error::RemoveWhat));
+
+    let (e, output) = h.err(U, remove_file, b"docs");
+    assert!(matches!(e, Error::RemoveFailed));
+    assert_eq!(output, b"docs not empty\n\n");
+
+    let (e, _) = h.err(U, make_directory, b"");
+    assert!(matches!(e, Error::MakeDirectoryWhat));
+
+    let (e, output) = h.err(U, make_directory, b"readme.txt");
+    assert!(matches!(e, Error::MakeDirectoryFailed));
+    assert_eq!(output, b"readme.txt already exists\n\n");
+}
+
+#[test]
+fn wait_does_nothing() {
+    let mut h = Harness::new();
+    assert!(h.ok(U, wait, b"").is_empty());
+}
@@ -0,0 +1,526 @@
+//
+// registry of commands driving dispatch, help and completion
+//
+use crate::actions::{self, ActionContext, Error, Result};
+use crate::completion::Argument;
+
+pub type Action = fn(&mut ActionContext) -> Result<()>;
+
+/// Who a command is intended for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Privilege {
+    /// Playing the game.
+    Player,
+    /// Building the world.
+    Builder,
+    /// Accessing the hardware.
+    System,
+}
+
+pub struct Command {
+    /// Name followed by aliases.
+    pub names: &'static [&'static [u8]],
+    pub synopsis: &'static [u8],
+    pub description: &'static [u8],
+    pub privilege: Privilege,
+    /// What completes the arguments in order.
+    pub arguments: &'static [Argument],
+    pub action: Action,
+}
+
+pub static COMMANDS: &[Command] = &[
+    Command {
+        names: &[b"go"],
+        synopsis: b"<exit>",
+        description: b"go",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Exit],
+        action: actions::go,
+    },
+    Command {
+        names: &[b"n"],
+        synopsis: b"",
+        description: b"go north",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::go_north,
+    },
+    Command {
+        names: &[b"e"],
+        synopsis: b"",
+        description: b"go east",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::go_east,
+    },
+    Command {
+        names: &[b"s"],
+        synopsis: b"",
+        description: b"go south",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::go_south,
+    },
+    Command {
+        names: &[b"w"],
+        synopsis: b"",
+        description: b"go west",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::go_west,
+    },
+    Command {
+        names: &[b"goto"],
+        synopsis: b"<location>",
+        description: b"go to location by the shortest route",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Location],
+        action: actions::goto,
+    },
+    Command {
+        names: &[b"route"],
+        synopsis: b"<location>",
+        description: b"print the shortest route to location",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Location],
+        action: actions::route,
+    },
+    Command {
+        names: &[b"map"],
+        synopsis: b"",
+        description: b"print a map of locations around",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::map,
+    },
+    Command {
+        names: &[b"i", b"inventory"],
+        synopsis: b"",
+        description: b"display inventory",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::inventory,
+    },
+    Command {
+        names: &[b"x", b"examine"],
+        synopsis: b"<thing>",
+        description: b"examine object or entity",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Thing],
+        action: actions::examine,
+    },
+    Command {
+        names: &[b"t", b"take"],
+        synopsis: b"<object> [from <container>]",
+        description: b"take object",
+        privilege: Privilege::Player,
+        arguments: &[
+            Argument::ObjectInLocation,
+            Argument::Words(&[b"from"]),
+            Argument::Container,
+        ],
+        action: actions::take,
+    },
+    Command {
+        names: &[b"d", b"drop"],
+        synopsis: b"<object>",
+        description: b"drop object",
+        privilege: Privilege::Player,
+        arguments: &[Argument::ObjectInInventory],
+        action: actions::drop,
+    },
+    Command {
+        names: &[b"put"],
+        synopsis: b"<object> in <container>",
+        description: b"put object from inventory in container",
+        privilege: Privilege::Player,
+        arguments: &[
+            Argument::ObjectInInventory,
+            Argument::Words(&[b"in"]),
+            Argument::Container,
+        ],
+        action: actions::put,
+    },
+    Command {
+        names: &[b"g", b"give"],
+        synopsis: b"<entity> <object>",
+        description: b"give entity object from inventory",
+        privilege: Privilege::Player,
+        arguments: &[Argument::EntityInLocation, Argument::ObjectInInventory],
+        action: actions::give,
+    },
+    Command {
+        names: &[b"open"],
+        synopsis: b"<exit>",
+        description: b"open door",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Exit],
+        action: actions::open,
+    },
+    Command {
+        names: &[b"close"],
+        synopsis: b"<exit>",
+        description: b"close door",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Exit],
+        action: actions::close,
+    },
+    Command {
+        names: &[b"lock"],
+        synopsis: b"<exit>",
+        description: b"lock door with key from inventory",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Exit],
+        action: actions::lock,
+    },
+    Command {
+        names: &[b"unlock"],
+        synopsis: b"<exit>",
+        description: b"unlock door with key from inventory",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Exit],
+        action: actions::unlock,
+    },
+    Command {
+        names: &[b"say"],
+        synopsis: b"<what>",
+        description: b"say to all in location",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::say,
+    },
+    Command {
+        names: &[b"tell"],
+        synopsis: b"<whom> <what>",
+        description: b"tells entity in location",
+        privilege: Privilege::Player,
+        arguments: &[Argument::EntityInLocation],
+        action: actions::tell,
+    },
+    Command {
+        names: &[b"wait"],
+        synopsis: b"",
+        description: b"do nothing",
+        privilege: Privilege::Player,
+        arguments: &[],
+        action: actions::wait,
+    },
+    Command {
+        names: &[b"help"],
+        synopsis: b"[command]",
+        description: b"this message or help on command",
+        privilege: Privilege::Player,
+        arguments: &[Argument::Command],
+        action: help,
+    },
+    Command {
+        names: &[b"sln"],
+        synopsis: b"<text>",
+        description: b"set location note",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::set_location_note,
+    },
+    Command {
+        names: &[b"no"],
+        synopsis: b"<object name>",
+        description: b"new object into current inventory",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::new_object,
+    },
+    Command {
+        names: &[b"nl"],
+        synopsis: b"<to link> <back link> <new location name>",
+        description: b"new linked location",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::new_location,
+    },
+    Command {
+        names: &[b"ne"],
+        synopsis: b"<name>",
+        description: b"create new entity",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::new_entity,
+    },
+    Command {
+        names: &[b"link"],
+        synopsis: b"<exit> <location>",
+        description: b"new one-way exit to location",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::LinkName, Argument::Location],
+        action: actions::new_link,
+    },
+    Command {
+        names: &[b"container"],
+        synopsis: b"<object>",
+        description: b"make object a container",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Object],
+        action: actions::new_container,
+    },
+    Command {
+        names: &[b"so"],
+        synopsis: b"<object> <weight> <bulk> [capacity]",
+        description: b"set object weight, bulk and bulk that fits in it",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Object],
+        action: actions::set_object,
+    },
+    Command {
+        names: &[b"se"],
+        synopsis: b"<entity> <max weight> <max bulk>",
+        description: b"set weight and bulk entity can carry",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Entity],
+        action: actions::set_entity,
+    },
+    Command {
+        names: &[b"door"],
+        synopsis: b"<exit> [key]",
+        description: b"closed door on exit and its link back, locked if key object",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Exit, Argument::Object],
+        action: actions::new_door,
+    },
+    Command {
+        names: &[b"rmo"],
+        synopsis: b"<object>",
+        description: b"remove object",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Object],
+        action: actions::remove_object,
+    },
+    Command {
+        names: &[b"rml"],
+        synopsis: b"<location>",
+        description: b"remove location and its objects, entities in it move here",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Location],
+        action: actions::remove_location,
+    },
+    Command {
+        names: &[b"rme"],
+        synopsis: b"<entity>",
+        description: b"remove entity and its inventory",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Entity],
+        action: actions::remove_entity,
+    },
+    Command {
+        names: &[b"unlink"],
+        synopsis: b"<exit>",
+        description: b"remove exit and the link back",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Exit],
+        action: actions::unlink,
+    },
+    Command {
+        names: &[b"rename"],
+        synopsis: b"<object|location|entity> <name> <new name>",
+        description: b"rename object, location or entity",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Words(&[b"object", b"location", b"entity"])],
+        action: actions::rename,
+    },
+    Command {
+        names: &[b"describe"],
+        synopsis: b"<thing> [text]",
+        description: b"set description of object or entity here, no text clears it",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Thing],
+        action: actions::describe,
+    },
+    Command {
+        names: &[b"save"],
+        synopsis: b"",
+        description: b"save world to SD card",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::save,
+    },
+    Command {
+        names: &[b"load"],
+        synopsis: b"",
+        description: b"load world from SD card",
+        privilege: Privilege::Builder,
+        arguments: &[],
+        action: actions::load,
+    },
+    Command {
+        names: &[b"export"],
+        synopsis: b"<dot|json>",
+        description: b"print world as graphviz dot or json",
+        privilege: Privilege::Builder,
+        arguments: &[Argument::Words(&[b"dot", b"json"])],
+        action: actions::export,
+    },
+    Command {
+        names: &[b"sds"],
+        synopsis: b"",
+        description: b"SD card status (6 is ok)",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::sdcard_status,
+    },
+    Command {
+        names: &[b"sdr"],
+        synopsis: b"<sector>",
+        description: b"read sector from SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::sdcard_read,
+    },
+    Command {
+        names: &[b"sdw"],
+        synopsis: b"<sector> <text>",
+        description: b"write sector to SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::sdcard_write,
+    },
+    Command {
+        names: &[b"ls"],
+        synopsis: b"[path]",
+        description: b"list directory on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::list,
+    },
+    Command {
+        names: &[b"cat"],
+        synopsis: b"<file>",
+        description: b"print file on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::cat,
+    },
+    Command {
+        names: &[b"cd"],
+        synopsis: b"[path]",
+        description: b"change directory on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::change_directory,
+    },
+    Command {
+        names: &[b"write"],
+        synopsis: b"<file> [text]",
+        description: b"write line to file on SD card or truncate it",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::write_file,
+    },
+    Command {
+        names: &[b"append"],
+        synopsis: b"<file> <text>",
+        description: b"append line to file on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::append_file,
+    },
+    Command {
+        names: &[b"rm"],
+        synopsis: b"<path>",
+        description: b"remove file or empty directory on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::remove_file,
+    },
+    Command {
+        names: &[b"mkdir"],
+        synopsis: b"<path>",
+        description: b"make directory on SD card",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::make_directory,
+    },
+    Command {
+        names: &[b"led"],
+        synopsis: b"<bits with 1 being on>",
+        description: b"turn on/off leds",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::led_set,
+    },
+    Command {
+        names: &[b"mi"],
+        synopsis: b"[blocks]",
+        description: b"memory allocation info or heap blocks",
+        privilege: Privilege::System,
+        arguments: &[Argument::Words(&[b"blocks"])],
+        action: actions::memory_info,
+    },
+    Command {
+        names: &[b"heapcheck"],
+        synopsis: b"",
+        description: b"check heap integrity",
+        privilege: Privilege::System,
+        arguments: &[],
+        action: actions::heap_check,
+    },
+];
+
+/// Returns the command with name or alias `name`.
+pub fn find(name: &[u8]) -> Option<&'static Command> {
+    COMMANDS.iter().find(|x| x.names.contains(&name))
+}
+
+/// Executes the command named by the first token.
+pub fn handle_input(ctx: &mut ActionContext) -> Result<()> {
+    let Some(command) = ctx.tokens.next().and_then(find) else {
+        ctx.printer.p(b"not understood");
+        ctx.printer.nlc(2);
+        return Err(Error::NotUnderstood);
+    };
+
+    (command.action)(ctx)?;
+
+    ctx.printer.nl();
+
+    Ok(())
+}
+
+pub fn help(ctx: &mut ActionContext) -> Result<()> {
+    if let Some(name) = ctx.tokens.next() {
+        let Some(command) = find(name) else {
+            ctx.printer.p(name);
+            ctx.printer.p(b" is not a command");
+            ctx.printer.nlc(2);
+            return Err(Error::NoSuchCommand);
+        };
+        print_command(ctx, command);
+        return Ok(());
+    }
+
+    for (privilege, heading) in [
+        (Privilege::Player, b"command:".as_slice()),
+        (Privilege::Builder, b"builder:"),
+        (Privilege::System, b"system:"),
+    ] {
+        ctx.printer.pl(heading);
+        for command in COMMANDS.iter().filter(|x| x.privilege == privilege) {
+            print_command(ctx, command);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_command(ctx: &ActionContext, command: &Command) {
+    ctx.printer.p(b"  ");
+    for (i, name) in command.names.iter().enumerate() {
+        if i != 0 {
+            ctx.printer.p(b", ");
+        }
+        ctx.printer.p(name);
+    }
+    if !command.synopsis.is_empty() {
+        ctx.printer.pb(b' ');
+        ctx.printer.p(command.synopsis);
+    }
+    ctx.printer.p(b": ");
+    ctx.printer.pl(command.description);
+}
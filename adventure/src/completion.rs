@@ -0,0 +1,392 @@
+//
+// completion of the word at the cursor in a command line
+//
+use crate::commands::{self, COMMANDS};
+use crate::model::{EntityId, Name, World};
+
+/// Names that complete an argument of a command.
+#[derive(Clone, Copy)]
+pub enum Argument {
+    Command,
+    Exit,
+    ObjectInLocation,
+    ObjectInInventory,
+    EntityInLocation,
+    /// Object in inventory or location or entity in location.
+    Thing,
+    /// Container in inventory or location.
+    Container,
+    /// Any object in the world.
+    Object,
+    /// Any location in the world.
+    Location,
+    /// Any entity in the world.
+    Entity,
+    /// Any link name in the world.
+    LinkName,
+    /// One of the words.
+    Words(&'static [&'static [u8]]),
+}
+
+/// Returns the start of the word that ends at `cursor`.
+pub fn word_start(line: &[u8], cursor: usize) -> usize {
+    line[..cursor]
+        .iter()
+        .rposition(u8::is_ascii_whitespace)
+        .map_or(0, |i| i + 1)
+}
+
+/// Calls `f` with each name that starts with the word ending at `cursor`.
+///
+/// The first word is completed with command names and the following words with what the
+/// command's arguments are, such as exits, objects or entities that `entity` can see.
+pub fn for_each_candidate<F>(world: &World, entity: EntityId, line: &[u8], cursor: usize, mut f: F)
+where
+    F: FnMut(&[u8]),
+{
+    let start = word_start(line, cursor);
+    let prefix = &line[start..cursor];
+    let mut words = line[..start]
+        .split(u8::is_ascii_whitespace)
+        .filter(|x| !x.is_empty());
+
+    let mut candidate = |name: &[u8]| {
+        if name.starts_with(prefix) {
+            f(name);
+        }
+    };
+
+    let Some(command) = words.next() else {
+        for_each_command_name(prefix, &mut candidate);
+        return;
+    };
+
+    if let Some(argument) = commands::find(command).and_then(|x| x.arguments.get(words.count())) {
+        for_each_argument_name(world, entity, argument, prefix, &mut candidate);
+    }
+}
+
+/// Calls `candidate` with each name that completes `argument` for `entity`.
+fn for_each_argument_name<F>(
+    world: &World,
+    entity: EntityId,
+    argument: &Argument,
+    prefix: &[u8],
+    candidate: &mut F,
+) where
+    F: FnMut(&[u8]),
+{
+    let Some(ent) = world.entities.get(entity) else {
+        return;
+    };
+    let Some(location) = world.locations.get(ent.location) else {
+        return;
+    };
+    match argument {
+        Argument::Command => for_each_command_name(prefix, candidate),
+        Argument::Exit => {
+            for x in location
+                .links
+                .iter()
+                .filter_map(|x| world.link_names.get(x.link_name))
+            {
+                candidate(&x.name);
+            }
+        }
+        Argument::ObjectInLocation => {
+            for x in location
+                .objects
+                .iter()
+                .filter_map(|&x| world.objects.get(x))
+            {
+                candidate(&x.name);
+            }
+        }
+        Argument::ObjectInInventory => {
+            for x in ent.objects.iter().filter_map(|&x| world.objects.get(x)) {
+                candidate(&x.name);
+            }
+        }
+        Argument::EntityInLocation => {
+            for x in location
+                .entities
+                .iter()
+                .filter(|&&x| x != entity)
+                .filter_map(|&x| world.entities.get(x))
+            {
+                candidate(&x.name);
+            }
+        }
+        Argument::Thing => {
+            for x in ent
+                .objects
+                .iter()
+                .chain(&location.objects)
+                .filter_map(|&x| world.objects.get(x))
+            {
+                candidate(&x.name);
+            }
+            for x in location
+                .entities
+                .iter()
+                .filter(|&&x| x != entity)
+                .filter_map(|&x| world.entities.get(x))
+            {
+                candidate(&x.name);
+            }
+        }
+        Argument::Container => {
+            for x in ent
+                .objects
+                .iter()
+                .chain(&location.objects)
+                .filter_map(|&x| world.objects.get(x))
+                .filter(|x| x.container)
+            {
+                candidate(&x.name);
+            }
+        }
+        Argument::Object => {
+            for x in world.objects.values() {
+                candidate(&x.name);
+            }
+        }
+        Argument::Location => {
+            for x in world.locations.values() {
+                candidate(&x.name);
+            }
+        }
+        Argument::Entity => {
+            for x in world.entities.values() {
+                candidate(&x.name);
+            }
+        }
+        Argument::LinkName => {
+            for x in world.link_names.values() {
+                candidate(&x.name);
+            }
+        }
+        Argument::Words(words) => {
+            for word in *words {
+                candidate(word);
+            }
+        }
+    }
+}
+
+/// Calls `f` with the first name of each command that starts with `prefix`.
+/// note: `prefix` naming a command is its only name so that it completes as unique
+fn for_each_command_name<F>(prefix: &[u8], f: &mut F)
+where
+    F: FnMut(&[u8]),
+{
+    if commands::find(prefix).is_some() {
+        f(prefix);
+        return;
+    }
+    for command in COMMANDS {
+        if let Some(name) = command.names.iter().find(|x| x.starts_with(prefix)) {
+            f(name);
+        }
+    }
+}
+
+/// Returns the number of candidates and their longest common prefix.
+pub fn complete(world: &World, entity: EntityId, line: &[u8], cursor: usize) -> (usize, Name) {
+    let mut count = 0;
+    let mut common = Name::new();
+    for_each_candidate(world, entity, line, cursor, |name| {
+        if count == 0 {
+            common = Name::from(name);
+        } else {
+            let len = common.iter().zip(name).take_while(|(a, b)| a == b).count();
+            common = Name::from(&common[..len]);
+        }
+        count += 1;
+    });
+
+    (count, common)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::arena::{Arena, Id};
+    use crate::model::{
+        DEFAULT_BULK, DEFAULT_CAPACITY, DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, DEFAULT_WEIGHT,
+        Entity, Link, LinkName, Location, Note, Object,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn world() -> World {
+        let name = |x: &[u8]| Name::from(x);
+        World {
+            objects: Arena::from(vec![
+                Object {
+                    name: name(b"lamp"),
+                    description: Note::default(),
+                    weight: DEFAULT_WEIGHT,
+                    bulk: DEFAULT_BULK,
+                    container: false,
+                    capacity: DEFAULT_CAPACITY,
+                    objects: vec![],
+                },
+                Object {
+                    name: name(b"ladder"),
+                    description: Note::default(),
+                    weight: DEFAULT_WEIGHT,
+                    bulk: DEFAULT_BULK,
+                    container: true,
+                    capacity: DEFAULT_CAPACITY,
+                    objects: vec![],
+                },
+                Object {
+                    name: name(b"pen"),
+                    description: Note::default(),
+                    weight: DEFAULT_WEIGHT,
+                    bulk: DEFAULT_BULK,
+                    container: false,
+                    capacity: DEFAULT_CAPACITY,
+                    objects: vec![],
+                },
+            ]),
+            entities: Arena::from(vec![
+                Entity {
+                    name: name(b"u"),
+                    description: Note::default(),
+                    location: Id::new(0, 0),
+                    objects: vec![Id::new(2, 0)],
+                    messages: vec![],
+                    max_weight: DEFAULT_MAX_WEIGHT,
+                    max_bulk: DEFAULT_MAX_BULK,
+                },
+                Entity {
+                    name: name(b"me"),
+                    description: Note::default(),
+                    location: Id::new(0, 0),
+                    objects: vec![],
+                    messages: vec![],
+                    max_weight: DEFAULT_MAX_WEIGHT,
+                    max_bulk: DEFAULT_MAX_BULK,
+                },
+            ]),
+            locations: Arena::from(vec![
+                Location {
+                    name: name(b"roome"),
+                    note: Note::default(),
+                    links: vec![
+                        Link {
+                            link_name: Id::new(0, 0),
+                            location: Id::new(1, 0),
+                            door: None,
+                        },
+                        Link {
+                            link_name: Id::new(1, 0),
+                            location: Id::new(1, 0),
+                            door: None,
+                        },
+                    ],
+                    objects: vec![Id::new(0, 0), Id::new(1, 0)],
+                    entities: vec![Id::new(0, 0), Id::new(1, 0)],
+                },
+                Location {
+                    name: name(b"hall"),
+                    note: Note::default(),
+                    links: vec![Link {
+                        link_name: Id::new(2, 0),
+                        location: Id::new(0, 0),
+                        door: None,
+                    }],
+                    objects: vec![],
+                    entities: vec![],
+                },
+            ]),
+            link_names: Arena::from(vec![
+                LinkName {
+                    name: name(b"north"),
+                },
+                LinkName {
+                    name: name(b"northeast"),
+                },
+                LinkName {
+                    name: name(b"south"),
+                },
+            ]),
+        }
+    }
+
+    /// Entity "u".
+    const U: EntityId = Id::new(0, 0);
+
+    fn candidates(line: &[u8]) -> Vec<Vec<u8>> {
+        let mut names = Vec::new();
+        for_each_candidate(&world(), U, line, line.len(), |x| {
+            names.push(x.to_vec());
+        });
+        names
+    }
+
+    #[test]
+    fn word_start_after_whitespace() {
+        assert_eq!(word_start(b"go no", 5), 3);
+        assert_eq!(word_start(b"go no", 2), 0);
+        assert_eq!(word_start(b"go ", 3), 3);
+    }
+
+    #[test]
+    fn first_word_completes_commands() {
+        assert_eq!(candidates(b"g"), [b"g"]);
+        assert_eq!(candidates(b"go"), [b"go"]);
+        assert_eq!(candidates(b"gi"), [b"give"]);
+        assert_eq!(candidates(b"in"), [b"inventory"]);
+        assert_eq!(candidates(b"").len(), COMMANDS.len());
+        assert_eq!(candidates(b"  te"), [b"tell"]);
+        assert_eq!(candidates(b"help loa"), [b"load"]);
+    }
+
+    #[test]
+    fn arguments_complete_by_command() {
+        assert_eq!(candidates(b"go "), [b"north".as_slice(), b"northeast"]);
+        assert_eq!(candidates(b"t la"), [b"lamp".as_slice(), b"ladder"]);
+        assert_eq!(candidates(b"d "), [b"pen"]);
+        assert_eq!(candidates(b"tell "), [b"me"]);
+        assert_eq!(candidates(b"g me "), [b"pen"]);
+        assert!(candidates(b"say ").is_empty());
+        assert!(candidates(b"go north ").is_empty());
+        assert_eq!(candidates(b"mi b"), [b"blocks"]);
+        assert_eq!(candidates(b"rmo l"), [b"lamp".as_slice(), b"ladder"]);
+        assert_eq!(candidates(b"rml h"), [b"hall"]);
+        assert_eq!(candidates(b"put pen "), [b"in"]);
+        assert_eq!(candidates(b"t lamp from "), [b"ladder"]);
+        assert_eq!(candidates(b"link n"), [b"north".as_slice(), b"northeast"]);
+        assert_eq!(candidates(b"link up r"), [b"roome"]);
+        assert_eq!(candidates(b"x l"), [b"lamp".as_slice(), b"ladder"]);
+        assert_eq!(
+            candidates(b"x "),
+            [b"pen".as_slice(), b"lamp", b"ladder", b"me"]
+        );
+        assert_eq!(candidates(b"rename l"), [b"location"]);
+        assert_eq!(candidates(b"rme "), [b"u".as_slice(), b"me"]);
+    }
+
+    #[test]
+    fn complete_returns_common_prefix() {
+        let world = world();
+        let (count, common) = complete(&world, U, b"go n", 4);
+        assert_eq!(count, 2);
+        assert_eq!(&*common, b"north");
+
+        let (count, common) = complete(&world, U, b"t lam", 5);
+        assert_eq!(count, 1);
+        assert_eq!(&*common, b"lamp");
+
+        // completes the word ending at the cursor
+        let (count, common) = complete(&world, U, b"sa s", 2);
+        assert_eq!(count, 2);
+        assert_eq!(&*common, b"sa");
+
+        let (count, _) = complete(&world, U, b"t x", 3);
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,442 @@
+//
+// prints the world as graphviz dot or json
+//
+// note: printed while walking the world thus no output is built in memory, ids are slot indexes
+//       and references that are stale are left out
+//
+// dot: locations are boxes named l<id>, objects ellipses o<id> and entities diamonds e<id>
+//      links are solid edges labeled with the exit, what is held are dotted edges from the holder
+//
+// json: {"locations":[...],"objects":[...],"entities":[...]} with an element per line
+//
+use crate::lib::api::Printer;
+use crate::lib::arena::{Arena, Id};
+use crate::model::{Door, DoorState, Link, World};
+
+pub fn dot(printer: &dyn Printer, world: &World) {
+    printer.pl(b"digraph world {");
+
+    for (id, location) in world.locations.iter() {
+        let node = (b'l', id.index());
+        printer.p(b"  l");
+        printer.p_u32(id.index());
+        printer.p(b" [shape=box label=\"");
+        p_dot_string(printer, &location.name);
+        if !location.note.is_empty() {
+            printer.p(b"\\n");
+            p_dot_string(printer, &location.note);
+        }
+        printer.pl(b"\"];");
+
+        for link in &location.links {
+            let Some(link_name) = valid_link_name(world, link) else {
+                continue;
+            };
+            printer.p(b"  l");
+            printer.p_u32(id.index());
+            printer.p(b" -> l");
+            printer.p_u32(link.location.index());
+            printer.p(b" [label=\"");
+            p_dot_string(printer, link_name);
+            match link.door.map(|x| x.state) {
+                Some(DoorState::Open) => printer.p(b" (open)"),
+                Some(DoorState::Closed) => printer.p(b" (closed)"),
+                Some(DoorState::Locked) => printer.p(b" (locked)"),
+                None => {}
+            }
+            printer.pl(b"\"];");
+        }
+
+        p_dot_held(printer, node, &world.objects, &location.objects, b'o');
+        p_dot_held(printer, node, &world.entities, &location.entities, b'e');
+    }
+
+    for (id, object) in world.objects.iter() {
+        let node = (b'o', id.index());
+        printer.p(b"  o");
+        printer.p_u32(id.index());
+        printer.p(b" [shape=ellipse label=\"");
+        p_dot_string(printer, &object.name);
+        if !object.description.is_empty() {
+            printer.p(b"\\n");
+            p_dot_string(printer, &object.description);
+        }
+        printer.pl(b"\"];");
+
+        p_dot_held(printer, node, &world.objects, &object.objects, b'o');
+    }
+
+    for (id, entity) in world.entities.iter() {
+        let node = (b'e', id.index());
+        printer.p(b"  e");
+        printer.p_u32(id.index());
+        printer.p(b" [shape=diamond label=\"");
+        p_dot_string(printer, &entity.name);
+        if !entity.description.is_empty() {
+            printer.p(b"\\n");
+            p_dot_string(printer, &entity.description);
+        }
+        printer.pl(b"\"];");
+
+        p_dot_held(printer, node, &world.objects, &entity.objects, b'o');
+    }
+
+    printer.pl(b"}");
+}
+
+pub fn json(printer: &dyn Printer, world: &World) {
+    printer.pl(b"{");
+
+    printer.pl(b"\"locations\":[");
+    for (i, (id, location)) in world.locations.iter().enumerate() {
+        if i != 0 {
+            printer.pl(b",");
+        }
+        printer.p(b"{\"id\":");
+        printer.p_u32(id.index());
+        printer.p(b",\"name\":");
+        p_json_string(printer, &location.name);
+        printer.p(b",\"note\":");
+        p_json_string(printer, &location.note);
+
+        printer.p(b",\"links\":[");
+        let mut count = 0;
+        for link in &location.links {
+            let Some(link_name) = valid_link_name(world, link) else {
+                continue;
+            };
+            if count != 0 {
+                printer.p(b",");
+            }
+            count += 1;
+            printer.p(b"{\"name\":");
+            p_json_string(printer, link_name);
+            printer.p(b",\"to\":");
+            printer.p_u32(link.location.index());
+            printer.p(b",\"door\":");
+            p_json_door(printer, world, link.door);
+            printer.p(b"}");
+        }
+        printer.p(b"]");
+
+        printer.p(b",\"objects\":");
+        p_json_ids(printer, &world.objects, &location.objects);
+        printer.p(b",\"entities\":");
+        p_json_ids(printer, &world.entities, &location.entities);
+        printer.p(b"}");
+    }
+    if !world.locations.is_empty() {
+        printer.nl();
+    }
+    printer.pl(b"],");
+
+    printer.pl(b"\"objects\":[");
+    for (i, (id, object)) in world.objects.iter().enumerate() {
+        if i != 0 {
+            printer.pl(b",");
+        }
+        printer.p(b"{\"id\":");
+        printer.p_u32(id.index());
+        printer.p(b",\"name\":");
+        p_json_string(printer, &object.name);
+        printer.p(b",\"description\":");
+        p_json_string(printer, &object.description);
+        printer.p(b",\"weight\":");
+        printer.p_u32(object.weight);
+        printer.p(b",\"bulk\":");
+        printer.p_u32(object.bulk);
+        printer.p(b",\"container\":");
+        printer.p(if object.container { b"true" } else { b"false" });
+        printer.p(b",\"capacity\":");
+        printer.p_u32(object.capacity);
+        printer.p(b",\"objects\":");
+        p_json_ids(printer, &world.objects, &object.objects);
+        printer.p(b"}");
+    }
+    if !world.objects.is_empty() {
+        printer.nl();
+    }
+    printer.pl(b"],");
+
+    printer.pl(b"\"entities\":[");
+    for (i, (id, entity)) in world.entities.iter().enumerate() {
+        if i != 0 {
+            printer.pl(b",");
+        }
+        printer.p(b"{\"id\":");
+        printer.p_u32(id.index());
+        printer.p(b",\"name\":");
+        p_json_string(printer, &entity.name);
+        printer.p(b",\"description\":");
+        p_json_string(printer, &entity.description);
+        printer.p(b",\"location\":");
+        if world.locations.contains(entity.location) {
+            printer.p_u32(entity.location.index());
+        } else {
+            printer.p(b"null");
+        }
+        printer.p(b",\"max_weight\":");
+        printer.p_u32(entity.max_weight);
+        printer.p(b",\"max_bulk\":");
+        printer.p_u32(entity.max_bulk);
+        printer.p(b",\"objects\":");
+        p_json_ids(printer, &world.objects, &entity.objects);
+        printer.p(b"}");
+    }
+    if !world.entities.is_empty() {
+        printer.nl();
+    }
+    printer.pl(b"]");
+
+    printer.pl(b"}");
+}
+
+/// Returns the name of the link or `None` if the link name or the location is stale.
+fn valid_link_name<'a>(world: &'a World, link: &Link) -> Option<&'a [u8]> {
+    if !world.locations.contains(link.location) {
+        return None;
+    }
+    world.link_names.get(link.link_name).map(|x| &*x.name)
+}
+
+/// Prints dotted edges from the holder, given as node prefix and id, to what it holds.
+fn p_dot_held<T>(
+    printer: &dyn Printer,
+    (from_prefix, from_index): (u8, u32),
+    arena: &Arena<T>,
+    ids: &[Id<T>],
+    to_prefix: u8,
+) {
+    for &id in ids.iter().filter(|&&x| arena.contains(x)) {
+        printer.p(b"  ");
+        printer.pb(from_prefix);
+        printer.p_u32(from_index);
+        printer.p(b" -> ");
+        printer.pb(to_prefix);
+        printer.p_u32(id.index());
+        printer.pl(b" [style=dotted];");
+    }
+}
+
+/// Prints the bytes of a quoted dot string.
+fn p_dot_string(printer: &dyn Printer, bytes: &[u8]) {
+    for &byte in bytes {
+        if matches!(byte, b'"' | b'\\') {
+            printer.pb(b'\\');
+        }
+        printer.pb(byte);
+    }
+}
+
+/// Prints `null` or the door with its state and key.
+fn p_json_door(printer: &dyn Printer, world: &World, door: Option<Door>) {
+    let Some(door) = door else {
+        printer.p(b"null");
+        return;
+    };
+    printer.p(b"{\"state\":");
+    printer.p(match door.state {
+        DoorState::Open => b"\"open\"",
+        DoorState::Closed => b"\"closed\"",
+        DoorState::Locked => b"\"locked\"",
+    });
+    printer.p(b",\"key\":");
+    match door.key.filter(|&x| world.objects.contains(x)) {
+        Some(key) => printer.p_u32(key.index()),
+        None => printer.p(b"null"),
+    }
+    printer.p(b"}");
+}
+
+/// Prints an array of the ids that are not stale.
+fn p_json_ids<T>(printer: &dyn Printer, arena: &Arena<T>, ids: &[Id<T>]) {
+    printer.p(b"[");
+    for (i, &id) in ids.iter().filter(|&&x| arena.contains(x)).enumerate() {
+        if i != 0 {
+            printer.p(b",");
+        }
+        printer.p_u32(id.index());
+    }
+    printer.p(b"]");
+}
+
+/// Prints the bytes as a json string.
+/// note: bytes that are not printable ascii are printed as the code point of the same value
+fn p_json_string(printer: &dyn Printer, bytes: &[u8]) {
+    printer.pb(b'"');
+    for &byte in bytes {
+        match byte {
+            b'"' | b'\\' => {
+                printer.pb(b'\\');
+                printer.pb(byte);
+            }
+            b' '..=b'~' => printer.pb(byte),
+            _ => {
+                printer.p(b"\\u00");
+                printer.p_hex_u8(byte);
+            }
+        }
+    }
+    printer.pb(b'"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api_memory::PrinterMemory;
+    use crate::model::{
+        DEFAULT_BULK, DEFAULT_CAPACITY, DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, DEFAULT_WEIGHT,
+        Entity, LinkName, Location, Name, Note, Object,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn world() -> World {
+        World {
+            objects: Arena::from(vec![
+                Object {
+                    name: Name::from(b"bag"),
+                    description: Note::default(),
+                    weight: DEFAULT_WEIGHT,
+                    bulk: DEFAULT_BULK,
+                    container: true,
+                    capacity: DEFAULT_CAPACITY,
+                    objects: vec![Id::new(1, 0)],
+                },
+                Object {
+                    name: Name::from(b"key"),
+                    description: Note::from(b"\"old\""),
+                    weight: DEFAULT_WEIGHT,
+                    bulk: DEFAULT_BULK,
+                    container: false,
+                    capacity: DEFAULT_CAPACITY,
+                    objects: vec![],
+                },
+            ]),
+            entities: Arena::from(vec![Entity {
+                name: Name::from(b"u"),
+                description: Note::default(),
+                location: Id::new(1, 0),
+                objects: vec![Id::new(0, 0)],
+                messages: vec![],
+                max_weight: DEFAULT_MAX_WEIGHT,
+                max_bulk: DEFAULT_MAX_BULK,
+            }]),
+            locations: Arena::from(vec![
+                Location {
+                    name: Name::from(b"roome"),
+                    note: Note::from(b"dusty"),
+                    links: vec![Link {
+                        link_name: Id::new(0, 0),
+                        location: Id::new(1, 0),
+                        door: Some(Door {
+                            state: DoorState::Locked,
+                            key: Some(Id::new(1, 0)),
+                        }),
+                    }],
+                    objects: vec![],
+                    entities: vec![],
+                },
+                Location {
+                    name: Name::from(b"hall"),
+                    note: Note::default(),
+                    links: vec![
+                        Link {
+                            link_name: Id::new(1, 0),
+                            location: Id::new(0, 0),
+                            door: Some(Door {
+                                state: DoorState::Locked,
+                                key: Some(Id::new(1, 0)),
+                            }),
+                        },
+                        // note: stale location is left out
+                        Link {
+                            link_name: Id::new(0, 0),
+                            location: Id::new(7, 0),
+                            door: None,
+                        },
+                    ],
+                    objects: vec![],
+                    entities: vec![Id::new(0, 0)],
+                },
+            ]),
+            link_names: Arena::from(vec![
+                LinkName {
+                    name: Name::from(b"north"),
+                },
+                LinkName {
+                    name: Name::from(b"south"),
+                },
+            ]),
+        }
+    }
+
+    fn lines(output: &[u8]) -> Vec<&[u8]> {
+        output.split(|&x| x == b'\n').collect()
+    }
+
+    #[test]
+    fn dot_prints_locations_links_and_what_is_held() {
+        let printer = PrinterMemory::new();
+        dot(&printer, &world());
+        let output = printer.take();
+        assert_eq!(
+            lines(&output),
+            [
+                b"digraph world {".as_slice(),
+                b"  l0 [shape=box label=\"roome\\ndusty\"];",
+                b"  l0 -> l1 [label=\"north (locked)\"];",
+                b"  l1 [shape=box label=\"hall\"];",
+                b"  l1 -> l0 [label=\"south (locked)\"];",
+                b"  l1 -> e0 [style=dotted];",
+                b"  o0 [shape=ellipse label=\"bag\"];",
+                b"  o0 -> o1 [style=dotted];",
+                b"  o1 [shape=ellipse label=\"key\\n\\\"old\\\"\"];",
+                b"  e0 [shape=diamond label=\"u\"];",
+                b"  e0 -> o0 [style=dotted];",
+                b"}",
+                b"",
+            ]
+        );
+    }
+
+    #[test]
+    fn json_prints_an_element_per_line() {
+        let printer = PrinterMemory::new();
+        json(&printer, &world());
+        let output = printer.take();
+        assert_eq!(
+            lines(&output),
+            [
+                b"{".as_slice(),
+                b"\"locations\":[",
+                b"{\"id\":0,\"name\":\"roome\",\"note\":\"dusty\",\"links\":[{\"name\":\"north\",\
+                  \"to\":1,\"door\":{\"state\":\"locked\",\"key\":1}}],\"objects\":[],\
+                  \"entities\":[]},",
+                b"{\"id\":1,\"name\":\"hall\",\"note\":\"\",\"links\":[{\"name\":\"south\",\
+                  \"to\":0,\"door\":{\"state\":\"locked\",\"key\":1}}],\"objects\":[],\
+                  \"entities\":[0]}",
+                b"],",
+                b"\"objects\":[",
+                b"{\"id\":0,\"name\":\"bag\",\"description\":\"\",\"weight\":1,\"bulk\":1,\
+                  \"container\":true,\"capacity\":10,\"objects\":[1]},",
+                b"{\"id\":1,\"name\":\"key\",\"description\":\"\\\"old\\\"\",\"weight\":1,\
+                  \"bulk\":1,\"container\":false,\"capacity\":10,\"objects\":[]}",
+                b"],",
+                b"\"entities\":[",
+                b"{\"id\":0,\"name\":\"u\",\"description\":\"\",\"location\":1,\
+                  \"max_weight\":50,\"max_bulk\":10,\"objects\":[0]}",
+                b"]",
+                b"}",
+                b"",
+            ]
+        );
+    }
+
+    #[test]
+    fn json_escapes_strings() {
+        let printer = PrinterMemory::new();
+        p_json_string(&printer, b"a\"b\\c\td");
+        assert_eq!(printer.take(), b"\"a\\\"b\\\\c\\u0009d\"");
+    }
+}
@@ -0,0 +1,36 @@
+//
+// game logic of the firmware
+//
+// note: `no_std` and hardware is reached through `lib::api::Hardware` thus it can be tested on the host
+//
+#![no_std]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::must_use_candidate,
+    reason = "library is part of the firmware and not published"
+)]
+
+extern crate alloc;
+
+pub mod lib {
+    pub mod api;
+    #[cfg(test)]
+    pub mod api_memory;
+    pub mod arena;
+    pub mod cursor_buffer;
+    pub mod cursor_buffer_history;
+    pub mod fat;
+    #[cfg(test)]
+    pub mod fat_image;
+    pub mod fixed_size_string;
+    pub mod format;
+    pub mod heap;
+    pub mod mbr;
+}
+pub mod actions;
+pub mod commands;
+pub mod completion;
+pub mod export;
+pub mod model;
+pub mod persistence;
+pub mod script;
@@ -0,0 +1,257 @@
+//
+// reviewed: 2025-04-21
+//           2026-08-21
+//
+pub const SDCARD_SECTOR_SIZE_BYTES: usize = 512;
+pub const SDCARD_STATUS_READY: i32 = 6;
+
+/// Hardware used by the actions.
+/// note: implemented with memory mapped IO by the firmware and in memory by the tests
+pub trait Hardware {
+    fn led_set(&mut self, bits_low_being_on: u32);
+
+    fn sdcard_status(&self) -> i32;
+
+    fn sdcard_read_blocking(&self, sector: u32, buffer_512_bytes: &mut [u8]);
+
+    fn sdcard_write_blocking(&mut self, sector: u32, buffer_512_bytes: &[u8]);
+
+    fn memory_heap_start(&self) -> u32;
+
+    fn memory_stack_pointer(&self) -> u32;
+
+    fn memory_end(&self) -> u32;
+
+    /// Lowest address of the stack that the heap never allocates.
+    fn memory_stack_bottom(&self) -> u32;
+
+    /// Maximum number of bytes of stack used since boot.
+    fn memory_stack_peak(&self) -> u32;
+
+    /// Prints the heap block list.
+    fn memory_debug_block_list(&self, printer: &dyn Printer);
+
+    /// Checks the heap block list, prints violations and returns their count.
+    fn memory_heap_check(&self, printer: &dyn Printer) -> u32;
+
+    /// Returns the heap allocator counters.
+    fn memory_heap_stats(&self) -> HeapStats;
+}
+
+/// Heap allocator counters and figures of the free blocks.
+#[derive(Default)]
+pub struct HeapStats {
+    pub allocations: u32,
+    pub frees: u32,
+    /// Bytes of blocks in use including headers.
+    pub in_use_bytes: u32,
+    pub peak_in_use_bytes: u32,
+    pub free_bytes: u32,
+    pub free_block_count: u32,
+    pub largest_free_block_bytes: u32,
+}
+
+impl HeapStats {
+    /// Percentage of free bytes that are not in the largest free block.
+    pub const fn fragmentation_percent(&self) -> u32 {
+        if self.free_bytes == 0 {
+            return 0;
+        }
+        // note: 32-bit division since 64-bit division adds to the firmware on RV32I
+        let largest_percent = match self.largest_free_block_bytes.checked_mul(100) {
+            Some(x) => x / self.free_bytes,
+            None => self.largest_free_block_bytes / (self.free_bytes / 100),
+        };
+        100 - largest_percent
+    }
+}
+
+pub trait Printer {
+    /// Prints a byte.
+    fn pb(&self, byte: u8);
+
+    /// Prints implementation specific new line.
+    fn nl(&self);
+
+    /// Prints a slice of bytes.
+    fn p(&self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.pb(byte);
+        }
+    }
+
+    /// Prints implementation specific multiple new lines.
+    fn nlc(&self, count: usize) {
+        for _ in 0..count {
+            self.nl();
+        }
+    }
+
+    /// Prints a slice of bytes followed by implementation specific new line.
+    fn pl(&self, bytes: &[u8]) {
+        self.p(bytes);
+        self.nl();
+    }
+
+    /// Prints a 4-bit unsigned integer as hexadecimal.
+    fn p_hex_nibble(&self, nibble: u8) {
+        if nibble < 10 {
+            self.pb(b'0' + nibble);
+        } else {
+            self.pb(b'A' + (nibble - 10));
+        }
+    }
+
+    /// Prints a 8-bit unsigned integer as hexadecimal.
+    fn p_hex_u8(&self, i: u8) {
+        self.p_hex_nibble(i >> 4);
+        self.p_hex_nibble(i & 0x0f);
+    }
+
+    /// Prints a 32-bit unsigned integer as hexadecimal.
+    #[allow(clippy::cast_possible_truncation, reason = "intended behavior")]
+    fn p_hex_u32(&self, i: u32, separate_half_words: bool) {
+        self.p_hex_u8((i >> 24) as u8);
+        self.p_hex_u8((i >> 16) as u8);
+        if separate_half_words {
+            self.pb(b':');
+        }
+        self.p_hex_u8((i >> 8) as u8);
+        self.p_hex_u8(i as u8);
+    }
+
+    /// Prints a 32-bit unsigned integer.
+    fn p_u32(&self, num: u32) {
+        if num == 0 {
+            self.pb(b'0');
+            return;
+        }
+
+        let mut n = num;
+        let mut buf = [0_u8; 10];
+        // note: 10 is maximum digits for u32
+        let mut pos = buf.len();
+        while n > 0 {
+            pos -= 1;
+            buf[pos] = b'0' + (n % 10) as u8;
+            n /= 10;
+        }
+
+        self.p(&buf[pos..]);
+    }
+}
+
+/// Printer that writes nothing.
+pub struct PrinterVoid;
+
+impl PrinterVoid {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PrinterVoid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Printer for PrinterVoid {
+    fn pb(&self, _: u8) {}
+    fn nl(&self) {}
+    fn nlc(&self, _: usize) {}
+    fn p(&self, _: &[u8]) {}
+    fn pl(&self, _: &[u8]) {}
+    fn p_hex_nibble(&self, _: u8) {}
+    fn p_hex_u8(&self, _: u8) {}
+    fn p_hex_u32(&self, _: u32, _: bool) {}
+    fn p_u32(&self, _: u32) {}
+}
+
+pub fn u8_slice_to_u32(number_as_str: &[u8]) -> u32 {
+    let mut num = 0;
+    for &ch in number_as_str {
+        if !ch.is_ascii_digit() {
+            break;
+        }
+        num = num * 10 + u32::from(ch - b'0');
+    }
+    num
+}
+
+/// Returns the number or `None` if `number_as_str` is empty, has a byte that is not a digit or
+/// does not fit in u32.
+pub fn u8_slice_to_u32_checked(number_as_str: &[u8]) -> Option<u32> {
+    if number_as_str.is_empty() {
+        return None;
+    }
+    let mut num: u32 = 0;
+    for &ch in number_as_str {
+        if !ch.is_ascii_digit() {
+            return None;
+        }
+        num = num.checked_mul(10)?.checked_add(u32::from(ch - b'0'))?;
+    }
+    Some(num)
+}
+
+pub fn u8_slice_bits_to_u32(binary_as_str: &[u8]) -> u32 {
+    if binary_as_str.is_empty() {
+        return 0;
+    }
+    let mut num = 0;
+    let mut bit_value = 1 << (binary_as_str.len() - 1);
+    for &ch in binary_as_str {
+        if ch != b'0' && ch != b'1' {
+            break;
+        }
+        if ch == b'1' {
+            num += bit_value;
+        }
+        bit_value >>= 1;
+    }
+    num
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api_memory::PrinterMemory;
+
+    #[test]
+    fn print_numbers() {
+        let printer = PrinterMemory::new();
+        printer.p_u32(0);
+        printer.pb(b' ');
+        printer.p_u32(4_294_967_295);
+        printer.pb(b' ');
+        printer.p_hex_u32(0x00ab_cdef, true);
+        printer.pb(b' ');
+        printer.p_hex_u32(0x1234_5678, false);
+        assert_eq!(printer.take(), b"0 4294967295 00AB:CDEF 12345678");
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(u8_slice_to_u32(b"1234"), 1234);
+        assert_eq!(u8_slice_to_u32(b"12x4"), 12);
+        assert_eq!(u8_slice_to_u32(b""), 0);
+    }
+
+    #[test]
+    fn parse_decimal_checked() {
+        assert_eq!(u8_slice_to_u32_checked(b"4294967295"), Some(u32::MAX));
+        assert_eq!(u8_slice_to_u32_checked(b"0"), Some(0));
+        assert_eq!(u8_slice_to_u32_checked(b"4294967296"), None);
+        assert_eq!(u8_slice_to_u32_checked(b"99999999999"), None);
+        assert_eq!(u8_slice_to_u32_checked(b"9x"), None);
+        assert_eq!(u8_slice_to_u32_checked(b""), None);
+    }
+
+    #[test]
+    fn parse_bits() {
+        assert_eq!(u8_slice_bits_to_u32(b"1011"), 0b1011);
+        assert_eq!(u8_slice_bits_to_u32(b"0001"), 1);
+        assert_eq!(u8_slice_bits_to_u32(b""), 0);
+    }
+}
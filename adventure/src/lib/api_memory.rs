@@ -0,0 +1,126 @@
+//
+// in memory implementations of the hardware used by tests
+//
+use super::api::{Hardware, HeapStats, Printer, SDCARD_SECTOR_SIZE_BYTES, SDCARD_STATUS_READY};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// Hardware with LEDs and SD card kept in memory.
+pub struct HardwareMemory {
+    pub leds: u32,
+    pub sdcard_status: i32,
+    pub sdcard: Vec<u8>,
+}
+
+impl HardwareMemory {
+    pub fn new(sdcard_sectors: usize) -> Self {
+        Self {
+            leds: 0,
+            sdcard_status: SDCARD_STATUS_READY,
+            sdcard: vec![0; sdcard_sectors * SDCARD_SECTOR_SIZE_BYTES],
+        }
+    }
+
+    pub fn sector(&self, sector: u32) -> &[u8] {
+        let start = sector as usize * SDCARD_SECTOR_SIZE_BYTES;
+        &self.sdcard[start..start + SDCARD_SECTOR_SIZE_BYTES]
+    }
+
+    pub fn sector_mut(&mut self, sector: u32) -> &mut [u8] {
+        let start = sector as usize * SDCARD_SECTOR_SIZE_BYTES;
+        &mut self.sdcard[start..start + SDCARD_SECTOR_SIZE_BYTES]
+    }
+}
+
+impl Hardware for HardwareMemory {
+    fn led_set(&mut self, bits_low_being_on: u32) {
+        self.leds = bits_low_being_on;
+    }
+
+    fn sdcard_status(&self) -> i32 {
+        self.sdcard_status
+    }
+
+    fn sdcard_read_blocking(&self, sector: u32, buffer_512_bytes: &mut [u8]) {
+        buffer_512_bytes.copy_from_slice(self.sector(sector));
+    }
+
+    fn sdcard_write_blocking(&mut self, sector: u32, buffer_512_bytes: &[u8]) {
+        self.sector_mut(sector).copy_from_slice(buffer_512_bytes);
+    }
+
+    fn memory_heap_start(&self) -> u32 {
+        0x0000_1000
+    }
+
+    fn memory_stack_pointer(&self) -> u32 {
+        0x007f_ff00
+    }
+
+    fn memory_end(&self) -> u32 {
+        0x0080_0000
+    }
+
+    fn memory_stack_bottom(&self) -> u32 {
+        0x007f_0000
+    }
+
+    fn memory_stack_peak(&self) -> u32 {
+        0x0000_0200
+    }
+
+    fn memory_debug_block_list(&self, printer: &dyn Printer) {
+        printer.pl(b"heap blocks not available");
+    }
+
+    fn memory_heap_check(&self, _: &dyn Printer) -> u32 {
+        0
+    }
+
+    fn memory_heap_stats(&self) -> HeapStats {
+        HeapStats {
+            allocations: 10,
+            frees: 4,
+            in_use_bytes: 600,
+            peak_in_use_bytes: 800,
+            free_bytes: 1000,
+            free_block_count: 3,
+            largest_free_block_bytes: 750,
+        }
+    }
+}
+
+/// Printer that records the output.
+pub struct PrinterMemory {
+    output: RefCell<Vec<u8>>,
+}
+
+impl PrinterMemory {
+    pub const fn new() -> Self {
+        Self {
+            output: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the output printed so far and clears it.
+    pub fn take(&self) -> Vec<u8> {
+        self.output.take()
+    }
+}
+
+impl Default for PrinterMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Printer for PrinterMemory {
+    fn pb(&self, byte: u8) {
+        self.output.borrow_mut().push(byte);
+    }
+
+    fn nl(&self) {
+        self.pb(b'\n');
+    }
+}
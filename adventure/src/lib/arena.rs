@@ -0,0 +1,287 @@
+//
+// collection that hands out typed ids and rejects ids of removed elements
+//
+// note: a removed element leaves a vacant slot with its generation incremented thus an id kept
+//       from before the removal no longer matches when the slot is reused
+// note: `get` and `get_mut` are not inlined since most actions call them, saves 652 B
+//
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Id of an element in an `Arena<T>`.
+pub struct Id<T> {
+    index: u32,
+    generation: u32,
+    // note: `fn() -> T` so that the id is `Copy`, `Send` and `Sync` regardless of `T`
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    /// Id of slot `index` in `generation`. An arena that never removed has generation 0.
+    pub const fn new(index: u32, generation: u32) -> Self {
+        Self {
+            index,
+            generation,
+            marker: PhantomData,
+        }
+    }
+
+    pub const fn index(self) -> u32 {
+        self.index
+    }
+
+    pub const fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+// note: implemented by hand since derive would require `T` to implement the traits
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    len: usize,
+}
+
+impl<T> Arena<T> {
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Number of elements.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots including vacant ones.
+    pub const fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the element or `None` if `id` is stale or out of range.
+    #[inline(never)]
+    pub fn get(&self, id: Id<T>) -> Option<&T> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|x| x.generation == id.generation)
+            .and_then(|x| x.value.as_ref())
+    }
+
+    /// Returns the element or `None` if `id` is stale or out of range.
+    #[inline(never)]
+    pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|x| x.generation == id.generation)
+            .and_then(|x| x.value.as_mut())
+    }
+
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Returns the id of the element in slot `index` or `None` if the slot is vacant or out of
+    /// range.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "slots are fewer than u32::MAX"
+    )]
+    pub fn id_at(&self, index: usize) -> Option<Id<T>> {
+        self.slots
+            .get(index)
+            .filter(|x| x.value.is_some())
+            .map(|x| Id::new(index as u32, x.generation))
+    }
+
+    /// Returns the id of the first element that matches `predicate`.
+    pub fn find<P>(&self, mut predicate: P) -> Option<Id<T>>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.iter().find_map(|(id, x)| predicate(x).then_some(id))
+    }
+
+    /// Reserves room so that `additional` inserts do not allocate.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let vacant = self.slots.len() - self.len;
+        self.slots.try_reserve(additional.saturating_sub(vacant))
+    }
+
+    /// Inserts `value` in the first vacant slot or in a new slot and returns its id.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "slots are fewer than u32::MAX"
+    )]
+    pub fn insert(&mut self, value: T) -> Id<T> {
+        self.len += 1;
+        if let Some(index) = self.slots.iter().position(|x| x.value.is_none()) {
+            let slot = &mut self.slots[index];
+            slot.value = Some(value);
+            return Id::new(index as u32, slot.generation);
+        }
+
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        Id::new((self.slots.len() - 1) as u32, 0)
+    }
+
+    /// Removes and returns the element or `None` if `id` is stale or out of range.
+    pub fn remove(&mut self, id: Id<T>) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(id.index as usize)
+            .filter(|x| x.generation == id.generation)?;
+        let value = slot.value.take()?;
+        // note: wraps after 2^32 removals from the same slot
+        slot.generation = slot.generation.wrapping_add(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Iterates over ids and elements in slot order.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "slots are fewer than u32::MAX"
+    )]
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, x)| {
+            x.value
+                .as_ref()
+                .map(|value| (Id::new(index as u32, x.generation), value))
+        })
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|x| x.value.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|x| x.value.as_mut())
+    }
+
+    /// Iterates over generation and element of each slot including vacant ones.
+    /// note: used to save the arena with its generations
+    pub fn slots(&self) -> impl Iterator<Item = (u32, Option<&T>)> {
+        self.slots.iter().map(|x| (x.generation, x.value.as_ref()))
+    }
+
+    /// Arena with the generation and element of each slot as returned by `slots`.
+    pub fn from_slots(slots: Vec<(u32, Option<T>)>) -> Self {
+        let len = slots.iter().filter(|(_, value)| value.is_some()).count();
+        Self {
+            slots: slots
+                .into_iter()
+                .map(|(generation, value)| Slot { generation, value })
+                .collect(),
+            len,
+        }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Arena with the elements in slots of generation 0 thus the id of element `i` is `Id::new(i, 0)`.
+impl<T> From<Vec<T>> for Arena<T> {
+    fn from(values: Vec<T>) -> Self {
+        let len = values.len();
+        Self {
+            slots: values
+                .into_iter()
+                .map(|value| Slot {
+                    generation: 0,
+                    value: Some(value),
+                })
+                .collect(),
+            len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn rejects_stale_id_after_slot_is_reused() {
+        let mut arena = Arena::new();
+        let a = arena.insert(b'a');
+        let b = arena.insert(b'b');
+        assert_eq!(arena.remove(a), Some(b'a'));
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.remove(a), None);
+
+        let c = arena.insert(b'c');
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(c), Some(&b'c'));
+        assert_eq!(arena.get(b), Some(&b'b'));
+        assert_eq!(arena.len(), 2);
+    }
+
+    #[test]
+    fn iterates_over_occupied_slots() {
+        let mut arena = Arena::from(vec![1, 2, 3]);
+        assert!(arena.remove(Id::new(1, 0)).is_some());
+
+        let items: Vec<_> = arena.iter().map(|(id, &x)| (id.index(), x)).collect();
+        assert_eq!(items, [(0, 1), (2, 3)]);
+        assert_eq!(arena.id_at(1), None);
+        assert_eq!(arena.id_at(2), Some(Id::new(2, 0)));
+        assert_eq!(arena.find(|&x| x == 3), Some(Id::new(2, 0)));
+        assert_eq!(arena.slot_count(), 3);
+    }
+
+    #[test]
+    fn round_trips_slots_with_generations() {
+        let mut arena = Arena::from(vec![1, 2]);
+        assert!(arena.remove(Id::new(0, 0)).is_some());
+
+        let slots = arena.slots().map(|(g, x)| (g, x.copied())).collect();
+        let mut restored = Arena::from_slots(slots);
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored.get(Id::new(0, 0)), None);
+        assert_eq!(restored.insert(3), Id::new(0, 1));
+    }
+}
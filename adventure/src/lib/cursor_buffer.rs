@@ -2,6 +2,7 @@
 // reviewed: 2025-04-21
 //           2026-08-21
 //
+#[derive(Clone, Copy)]
 pub struct CursorBuffer<const SIZE: usize, T> {
     buffer: [T; SIZE],
     end: usize,
@@ -124,6 +125,15 @@ where
         self.end == SIZE
     }
 
+    pub const fn is_empty(&self) -> bool {
+        self.end == 0
+    }
+
+    /// Returns the elements from start to end.
+    pub fn as_slice(&self) -> &[T] {
+        &self.buffer[..self.end]
+    }
+
     // Applies `f` on each element from cursor to end.
     pub fn for_each_from_cursor<F>(&self, f: F)
     where
@@ -158,6 +168,15 @@ where
     }
 }
 
+impl<const SIZE: usize, T> Default for CursorBuffer<SIZE, T>
+where
+    T: Default + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct CursorBufferIterator<'a, const SIZE: usize, T, F>
 where
     F: Fn(&T) -> bool,
@@ -205,3 +224,80 @@ where
         Some(&self.cmd_buf.buffer[start..end])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    type Buffer = CursorBuffer<8, u8>;
+
+    fn buffer(content: &[u8]) -> Buffer {
+        let mut buf = Buffer::new();
+        for &x in content {
+            assert!(buf.insert(x).is_ok());
+        }
+        buf
+    }
+
+    fn from_cursor(buf: &Buffer) -> Vec<u8> {
+        let out = core::cell::RefCell::new(Vec::new());
+        buf.for_each_from_cursor(|&x| out.borrow_mut().push(x));
+        out.take()
+    }
+
+    #[test]
+    fn insert_at_cursor() {
+        let mut buf = buffer(b"ac");
+        assert_eq!(buf.move_cursor_left(), 1);
+        assert!(buf.insert(b'b').is_ok());
+        assert_eq!(buf.as_slice(), b"abc");
+        assert_eq!(from_cursor(&buf), b"c");
+    }
+
+    #[test]
+    fn insert_when_full() {
+        let mut buf = buffer(b"12345678");
+        assert!(buf.is_full());
+        assert!(matches!(buf.insert(b'9'), Err(Error::BufferFull)));
+    }
+
+    #[test]
+    fn delete_and_backspace() {
+        let mut buf = buffer(b"abcd");
+        assert!(matches!(buf.delete(), Err(Error::CursorAtEnd)));
+
+        buf.move_cursor_left();
+        buf.move_cursor_left();
+        assert!(buf.delete().is_ok());
+        assert_eq!(buf.as_slice(), b"abd");
+
+        assert!(buf.backspace().is_ok());
+        assert_eq!(buf.as_slice(), b"ad");
+        assert_eq!(buf.elements_after_cursor_count(), 1);
+
+        buf.move_cursor_to_start_of_line();
+        assert!(matches!(buf.backspace(), Err(Error::CursorAtStart)));
+    }
+
+    #[test]
+    fn cursor_movement() {
+        let mut buf = buffer(b"abc");
+        assert_eq!(buf.move_cursor_right(), 0);
+        assert_eq!(buf.move_cursor_to_start_of_line(), 3);
+        assert_eq!(buf.move_cursor_left(), 0);
+        assert_eq!(buf.move_cursor_right(), 1);
+        assert_eq!(buf.move_cursor_to_end_of_line(), 2);
+    }
+
+    #[test]
+    fn iter_tokens_skips_delimiters() {
+        let buf = buffer(b"  go  n ");
+        let mut tokens = buf.iter_tokens(u8::is_ascii_whitespace);
+        assert_eq!(tokens.next(), Some(b"go".as_slice()));
+        assert_eq!(tokens.rest(), b"n ");
+        assert_eq!(tokens.next(), Some(b"n".as_slice()));
+        assert_eq!(tokens.next(), None);
+        assert_eq!(tokens.rest(), b"");
+    }
+}
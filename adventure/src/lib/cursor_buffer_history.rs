@@ -0,0 +1,184 @@
+//
+// fixed capacity ring of previous cursor buffers without heap allocations
+//
+use super::cursor_buffer::CursorBuffer;
+
+pub struct CursorBufferHistory<const COUNT: usize, const SIZE: usize, T> {
+    entries: [CursorBuffer<SIZE, T>; COUNT],
+    len: usize,
+    newest: usize,
+    browse: usize,
+    // note: 0 when not browsing, 1 for the newest entry, 2 for the one before etc
+    draft: CursorBuffer<SIZE, T>,
+    // note: the buffer being edited before browsing started
+}
+
+impl<const COUNT: usize, const SIZE: usize, T> CursorBufferHistory<COUNT, SIZE, T>
+where
+    T: Default + Copy + PartialEq,
+{
+    pub fn new() -> Self {
+        Self {
+            entries: [CursorBuffer::new(); COUNT],
+            len: 0,
+            newest: 0,
+            browse: 0,
+            draft: CursorBuffer::new(),
+        }
+    }
+
+    /// Adds a copy of `buffer` as the newest entry overwriting the oldest when full.
+    /// Empty buffers and repeats of the newest entry are not added.
+    /// Browsing restarts from the newest entry.
+    pub fn push(&mut self, buffer: &CursorBuffer<SIZE, T>) {
+        self.browse = 0;
+
+        if COUNT == 0
+            || buffer.is_empty()
+            || (self.len != 0 && self.entries[self.newest].as_slice() == buffer.as_slice())
+        {
+            return;
+        }
+
+        if self.len != 0 {
+            self.newest = (self.newest + 1) % COUNT;
+        }
+        self.entries[self.newest] = *buffer;
+        if self.len < COUNT {
+            self.len += 1;
+        }
+    }
+
+    /// Replaces `buffer` with the entry before the one being browsed.
+    /// The buffer being edited is kept and restored by browsing past the newest entry.
+    /// Returns `false` if there is no older entry.
+    pub const fn previous(&mut self, buffer: &mut CursorBuffer<SIZE, T>) -> bool {
+        if self.browse == self.len {
+            return false;
+        }
+
+        if self.browse == 0 {
+            self.draft = *buffer;
+        }
+        self.browse += 1;
+        *buffer = self.entry(self.browse);
+        buffer.move_cursor_to_end_of_line();
+
+        true
+    }
+
+    /// Replaces `buffer` with the entry after the one being browsed.
+    /// Returns `false` if not browsing.
+    pub const fn next(&mut self, buffer: &mut CursorBuffer<SIZE, T>) -> bool {
+        if self.browse == 0 {
+            return false;
+        }
+
+        self.browse -= 1;
+        *buffer = if self.browse == 0 {
+            self.draft
+        } else {
+            self.entry(self.browse)
+        };
+        buffer.move_cursor_to_end_of_line();
+
+        true
+    }
+
+    /// Returns entry where 1 is the newest.
+    const fn entry(&self, age: usize) -> CursorBuffer<SIZE, T> {
+        self.entries[(self.newest + COUNT - (age - 1)) % COUNT]
+    }
+}
+
+impl<const COUNT: usize, const SIZE: usize, T> Default for CursorBufferHistory<COUNT, SIZE, T>
+where
+    T: Default + Copy + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Buffer = CursorBuffer<8, u8>;
+    type History = CursorBufferHistory<3, 8, u8>;
+
+    fn buffer(content: &[u8]) -> Buffer {
+        let mut buf = Buffer::new();
+        for &x in content {
+            assert!(buf.insert(x).is_ok());
+        }
+        buf
+    }
+
+    #[test]
+    fn browse_back_and_forth_restores_draft() {
+        let mut history = History::new();
+        history.push(&buffer(b"one"));
+        history.push(&buffer(b"two"));
+
+        let mut buf = buffer(b"dra");
+        buf.move_cursor_left();
+        assert!(history.previous(&mut buf));
+        assert_eq!(buf.as_slice(), b"two");
+        assert_eq!(buf.elements_after_cursor_count(), 0);
+        assert!(history.previous(&mut buf));
+        assert_eq!(buf.as_slice(), b"one");
+        assert!(!history.previous(&mut buf));
+        assert_eq!(buf.as_slice(), b"one");
+
+        assert!(history.next(&mut buf));
+        assert_eq!(buf.as_slice(), b"two");
+        assert!(history.next(&mut buf));
+        assert_eq!(buf.as_slice(), b"dra");
+        assert!(!history.next(&mut buf));
+    }
+
+    #[test]
+    fn oldest_entry_is_overwritten_when_full() {
+        let mut history = History::new();
+        for line in [b"a", b"b", b"c", b"d"] {
+            history.push(&buffer(line));
+        }
+
+        let mut buf = Buffer::new();
+        for expected in [b"d", b"c", b"b"] {
+            assert!(history.previous(&mut buf));
+            assert_eq!(buf.as_slice(), expected);
+        }
+        assert!(!history.previous(&mut buf));
+    }
+
+    #[test]
+    fn empty_and_repeated_lines_are_not_added() {
+        let mut history = History::new();
+        history.push(&buffer(b""));
+        history.push(&buffer(b"x"));
+        history.push(&buffer(b"x"));
+
+        let mut buf = Buffer::new();
+        assert!(history.previous(&mut buf));
+        assert!(!history.previous(&mut buf));
+    }
+
+    #[test]
+    fn push_restarts_browsing() {
+        let mut history = History::new();
+        history.push(&buffer(b"a"));
+        history.push(&buffer(b"b"));
+
+        let mut buf = Buffer::new();
+        assert!(history.previous(&mut buf));
+        assert!(history.previous(&mut buf));
+        history.push(&buf);
+
+        assert!(history.previous(&mut buf));
+        assert_eq!(buf.as_slice(), b"a");
+        assert!(history.previous(&mut buf));
+        assert_eq!(buf.as_slice(), b"b");
+    }
+}
@@ -0,0 +1,1451 @@
+//
+// FAT16 and FAT32 file system on the first FAT partition of the SD card
+//
+// note: writes are ordered so that losing power between sector writes leaves at most lost
+//       clusters: file data, then the FATs, then the directory entry, then freeing clusters
+//
+use super::api::{Hardware, SDCARD_SECTOR_SIZE_BYTES, SDCARD_STATUS_READY};
+use super::fixed_size_string::FixedSizeString;
+use super::mbr;
+use core::cmp::min;
+use core::iter::repeat;
+use core::ops::ControlFlow;
+
+pub type Cluster = u32;
+
+/// Cluster of the root directory.
+/// note: same as in the ".." entry of directories in the root directory
+pub const ROOT: Cluster = 0;
+
+/// Name in 8.3 format such as "README.TXT".
+pub type FileName = FixedSizeString<12>;
+
+const DIRECTORY_ENTRY_SIZE: usize = 32;
+
+const ATTRIBUTE_VOLUME_ID: u8 = 0x08;
+const ATTRIBUTE_DIRECTORY: u8 = 0x10;
+const ATTRIBUTE_ARCHIVE: u8 = 0x20;
+
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xe5;
+const ENTRY_KANJI_E5: u8 = 0x05;
+
+/// Characters other than letters and digits allowed in 8.3 names.
+const NAME_SPECIAL_CHARACTERS: &[u8] = b"!#$%&'()-@^_`{}~";
+
+/// Date of entries since there is no real time clock.
+const DATE_1980_01_01: u16 = 0x0021;
+
+const FAT_FREE: u32 = 0;
+const FAT16_END_OF_CHAIN: u32 = 0xfff8;
+const FAT32_END_OF_CHAIN: u32 = 0x0fff_fff8;
+const FAT32_CLUSTER_MASK: u32 = 0x0fff_ffff;
+/// Written as end of chain and truncated to 16 bits on FAT16.
+const FAT_END_OF_CHAIN_MARK: u32 = 0x0fff_ffff;
+
+const FAT16_MIN_CLUSTERS: u32 = 4085;
+
+const FS_INFO_LEAD_SIGNATURE: u32 = 0x4161_5252;
+const FS_INFO_STRUCT_SIGNATURE: u32 = 0x6141_7272;
+const FS_INFO_FREE_COUNT: usize = 488;
+const FS_INFO_NEXT_FREE: usize = 492;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub enum Error {
+    CardNotReady,
+    NoFileSystem,
+    Unsupported,
+    Corrupt,
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+    AlreadyExists,
+    InvalidName,
+    DirectoryNotEmpty,
+    DirectoryFull,
+    Full,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Fat16,
+    Fat32,
+}
+
+pub struct Volume {
+    kind: Kind,
+    sectors_per_cluster: u32,
+    fat_first_sector: u32,
+    fat_count: u32,
+    fat_size: u32,
+    /// FAT16 root directory region.
+    root_first_sector: u32,
+    root_sector_count: u32,
+    /// FAT32 root directory cluster chain.
+    root_cluster: Cluster,
+    data_first_sector: u32,
+    cluster_count: u32,
+    /// FAT32 sector with free cluster count and next free cluster hint.
+    fs_info_sector: Option<u32>,
+    /// Where the search for a free cluster starts or 0 if not read yet.
+    next_free: Cluster,
+    /// Clusters allocated and freed since the free cluster count was updated.
+    allocated: u32,
+    freed: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub name: FileName,
+    pub attributes: u8,
+    pub cluster: Cluster,
+    pub size: u32,
+}
+
+/// Position in a file read a sector at a time.
+pub struct FileCursor {
+    cluster: Cluster,
+    /// Sector within `cluster` read next.
+    sector: u32,
+    remaining: u32,
+}
+
+/// Location of a directory entry.
+#[derive(Clone, Copy)]
+struct Slot {
+    sector: u32,
+    offset: usize,
+}
+
+/// Last read FAT sector.
+struct FatSector {
+    index: u32,
+    data: [u8; SDCARD_SECTOR_SIZE_BYTES],
+}
+
+impl FatSector {
+    const fn new() -> Self {
+        Self {
+            index: u32::MAX,
+            data: [0_u8; SDCARD_SECTOR_SIZE_BYTES],
+        }
+    }
+}
+
+impl Entry {
+    const fn root() -> Self {
+        Self {
+            name: FileName::new(),
+            attributes: ATTRIBUTE_DIRECTORY,
+            cluster: ROOT,
+            size: 0,
+        }
+    }
+
+    fn parse(raw: &[u8]) -> Self {
+        let mut name = FileName::from(trim_end_spaces(&raw[..8]));
+        if name.first() == Some(&ENTRY_KANJI_E5) {
+            name = FileName::from_parts(&[&[ENTRY_DELETED], &name[1..]]);
+        }
+        let extension = trim_end_spaces(&raw[8..11]);
+        if !extension.is_empty() {
+            name.append(b".");
+            name.append(extension);
+        }
+
+        let cluster_high = u16::from_le_bytes([raw[20], raw[21]]);
+        let cluster_low = u16::from_le_bytes([raw[26], raw[27]]);
+
+        Self {
+            name,
+            attributes: raw[11],
+            cluster: u32::from(cluster_high) << 16 | u32::from(cluster_low),
+            size: u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]),
+        }
+    }
+
+    pub const fn is_directory(&self) -> bool {
+        self.attributes & ATTRIBUTE_DIRECTORY != 0
+    }
+
+    /// Returns true if this is the "." or ".." entry of a directory.
+    pub fn is_dot(&self) -> bool {
+        &*self.name == b"." || &*self.name == b".."
+    }
+}
+
+/// Mounts the first FAT partition of the SD card.
+pub fn mount(hardware: &dyn Hardware) -> Result<Volume> {
+    if hardware.sdcard_status() != SDCARD_STATUS_READY {
+        return Err(Error::CardNotReady);
+    }
+
+    let partition = mbr::read(hardware)
+        .and_then(|x| x.into_iter().find(mbr::Partition::is_fat))
+        .ok_or(Error::NoFileSystem)?;
+
+    let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    hardware.sdcard_read_blocking(partition.first_sector, &mut sector);
+    if !mbr::has_signature(&sector) {
+        return Err(Error::NoFileSystem);
+    }
+
+    Volume::from_boot_sector(partition.first_sector, &sector)
+}
+
+impl Volume {
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    fn from_boot_sector(first_sector: u32, bs: &[u8]) -> Result<Self> {
+        let u16_at = |i: usize| u32::from(u16::from_le_bytes([bs[i], bs[i + 1]]));
+
+        if u16_at(11) as usize != SDCARD_SECTOR_SIZE_BYTES {
+            return Err(Error::Unsupported);
+        }
+
+        let sectors_per_cluster = u32::from(bs[13]);
+        let reserved_sector_count = u16_at(14);
+        let fat_count = u32::from(bs[16]);
+        let root_entry_count = u16_at(17);
+        let fat_size_16 = u16_at(22);
+        let total_sectors = match u16_at(19) {
+            0 => u32_at(bs, 32),
+            n => n,
+        };
+
+        if !sectors_per_cluster.is_power_of_two() || reserved_sector_count == 0 || fat_count == 0 {
+            return Err(Error::Corrupt);
+        }
+
+        // note: FAT32 is identified by its boot sector layout rather than the cluster count
+        let (kind, fat_size, root_cluster, fs_info_sector) = if fat_size_16 == 0 {
+            let fs_info = u16_at(48);
+            (
+                Kind::Fat32,
+                u32_at(bs, 36),
+                u32_at(bs, 44),
+                (fs_info != 0 && fs_info < reserved_sector_count).then_some(first_sector + fs_info),
+            )
+        } else {
+            (Kind::Fat16, fat_size_16, ROOT, None)
+        };
+
+        let root_sector_count = (root_entry_count * DIRECTORY_ENTRY_SIZE as u32)
+            .div_ceil(SDCARD_SECTOR_SIZE_BYTES as u32);
+        let system_sector_count = fat_count
+            .checked_mul(fat_size)
+            .and_then(|x| x.checked_add(reserved_sector_count + root_sector_count))
+            .ok_or(Error::Corrupt)?;
+        let cluster_count = total_sectors
+            .checked_sub(system_sector_count)
+            .ok_or(Error::Corrupt)?
+            / sectors_per_cluster;
+
+        if kind == Kind::Fat16 && cluster_count < FAT16_MIN_CLUSTERS {
+            // note: FAT12
+            return Err(Error::Unsupported);
+        }
+
+        let fat_first_sector = first_sector + reserved_sector_count;
+        let root_first_sector = fat_first_sector + fat_count * fat_size;
+
+        let volume = Self {
+            kind,
+            sectors_per_cluster,
+            fat_first_sector,
+            fat_count,
+            fat_size,
+            root_first_sector,
+            root_sector_count,
+            root_cluster,
+            data_first_sector: root_first_sector + root_sector_count,
+            cluster_count,
+            fs_info_sector,
+            next_free: 0,
+            allocated: 0,
+            freed: 0,
+        };
+
+        if kind == Kind::Fat32 && !volume.is_valid_cluster(root_cluster) {
+            return Err(Error::Corrupt);
+        }
+
+        Ok(volume)
+    }
+
+    /// Returns the entry at `path` relative to `directory` or the root if `path` starts
+    /// with '/'.
+    pub fn resolve(
+        &self,
+        hardware: &dyn Hardware,
+        directory: Cluster,
+        path: &[u8],
+    ) -> Result<Entry> {
+        let mut entry = Entry::root();
+        if !path.starts_with(b"/") {
+            entry.cluster = directory;
+        }
+
+        for name in path.split(|&x| x == b'/').filter(|x| !x.is_empty()) {
+            if !entry.is_directory() {
+                return Err(Error::NotADirectory);
+            }
+            if name == b"." || (entry.cluster == ROOT && name == b"..") {
+                // note: the root directory has no "." and ".." entries
+                continue;
+            }
+            entry = self.find(hardware, entry.cluster, name)?;
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns the entry named `name` in `directory` ignoring case.
+    pub fn find(&self, hardware: &dyn Hardware, directory: Cluster, name: &[u8]) -> Result<Entry> {
+        self.lookup(hardware, directory, name)?
+            .map(|(entry, _)| entry)
+            .ok_or(Error::NotFound)
+    }
+
+    /// Calls `f` with each entry in `directory` until it breaks.
+    /// note: skips deleted entries, long name entries and the volume label
+    pub fn for_each_entry<F>(
+        &self,
+        hardware: &dyn Hardware,
+        directory: Cluster,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Entry) -> ControlFlow<()>,
+    {
+        self.for_each_entry_slot(hardware, directory, |entry, _| f(entry))
+    }
+
+    /// Calls `f` with the content of the file `entry` in chunks of at most a sector.
+    pub fn read<F>(&self, hardware: &dyn Hardware, entry: &Entry, mut f: F) -> Result<()>
+    where
+        F: FnMut(&[u8]),
+    {
+        if entry.is_directory() {
+            return Err(Error::IsADirectory);
+        }
+
+        let mut remaining = entry.size as usize;
+        if remaining == 0 {
+            return Ok(());
+        }
+
+        if !self.is_valid_cluster(entry.cluster) {
+            return Err(Error::Corrupt);
+        }
+
+        let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        self.for_each_sector(hardware, entry.cluster, |sector_index| {
+            hardware.sdcard_read_blocking(sector_index, &mut sector);
+            let len = min(remaining, SDCARD_SECTOR_SIZE_BYTES);
+            f(&sector[..len]);
+            remaining -= len;
+            if remaining == 0 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+
+        if remaining != 0 {
+            // note: cluster chain shorter than file size
+            return Err(Error::Corrupt);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a cursor at the start of the file `entry`.
+    pub const fn open(&self, entry: &Entry) -> Result<FileCursor> {
+        if entry.is_directory() {
+            return Err(Error::IsADirectory);
+        }
+
+        if entry.size != 0 && !self.is_valid_cluster(entry.cluster) {
+            return Err(Error::Corrupt);
+        }
+
+        Ok(FileCursor {
+            cluster: entry.cluster,
+            sector: 0,
+            remaining: entry.size,
+        })
+    }
+
+    /// Reads the next sector of the file at `cursor` into `buffer` and returns the number of
+    /// bytes read or 0 at end of file.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    pub fn read_next(
+        &self,
+        hardware: &dyn Hardware,
+        cursor: &mut FileCursor,
+        buffer: &mut [u8; SDCARD_SECTOR_SIZE_BYTES],
+    ) -> Result<usize> {
+        if cursor.remaining == 0 {
+            return Ok(0);
+        }
+
+        if cursor.sector == self.sectors_per_cluster {
+            cursor.cluster = self
+                .next_cluster(hardware, cursor.cluster)
+                .filter(|&x| self.is_valid_cluster(x))
+                .ok_or(Error::Corrupt)?;
+            cursor.sector = 0;
+        }
+
+        hardware.sdcard_read_blocking(
+            self.cluster_first_sector(cursor.cluster) + cursor.sector,
+            buffer,
+        );
+        cursor.sector += 1;
+
+        let len = min(cursor.remaining as usize, SDCARD_SECTOR_SIZE_BYTES);
+        cursor.remaining -= len as u32;
+
+        Ok(len)
+    }
+
+    /// Replaces the content of the file at `path` with `data` creating the file if needed.
+    pub fn write(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        directory: Cluster,
+        path: &[u8],
+        data: &[u8],
+    ) -> Result<()> {
+        let (parent, name) = self.resolve_parent(hardware, directory, path)?;
+
+        match self.lookup(hardware, parent, name)? {
+            Some((entry, _)) if entry.is_directory() => return Err(Error::IsADirectory),
+            Some((entry, slot)) => self.replace(hardware, &entry, slot, data)?,
+            None => self.create_file(hardware, parent, name, data)?,
+        }
+
+        self.update_fs_info(hardware);
+
+        Ok(())
+    }
+
+    /// Appends `data` to the file at `path` creating the file if needed.
+    pub fn append(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        directory: Cluster,
+        path: &[u8],
+        data: &[u8],
+    ) -> Result<()> {
+        let (parent, name) = self.resolve_parent(hardware, directory, path)?;
+
+        match self.lookup(hardware, parent, name)? {
+            Some((entry, _)) if entry.is_directory() => return Err(Error::IsADirectory),
+            Some((entry, slot)) if entry.size == 0 => self.replace(hardware, &entry, slot, data)?,
+            Some((entry, slot)) => self.extend(hardware, &entry, slot, data)?,
+            None => self.create_file(hardware, parent, name, data)?,
+        }
+
+        self.update_fs_info(hardware);
+
+        Ok(())
+    }
+
+    /// Removes the file or empty directory at `path`.
+    pub fn remove(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        directory: Cluster,
+        path: &[u8],
+    ) -> Result<()> {
+        let (parent, name) = self.resolve_parent(hardware, directory, path)?;
+        let (entry, slot) = self
+            .lookup(hardware, parent, name)?
+            .ok_or(Error::NotFound)?;
+
+        if entry.is_dot() {
+            return Err(Error::InvalidName);
+        }
+
+        if entry.is_directory() {
+            let mut empty = true;
+            self.for_each_entry(hardware, entry.cluster, |x| {
+                if x.is_dot() {
+                    return ControlFlow::Continue(());
+                }
+                empty = false;
+                ControlFlow::Break(())
+            })?;
+            if !empty {
+                return Err(Error::DirectoryNotEmpty);
+            }
+        }
+
+        modify_slot(hardware, slot, |raw| raw[0] = ENTRY_DELETED);
+
+        if self.is_valid_cluster(entry.cluster) {
+            self.free_chain(hardware, entry.cluster)?;
+        }
+
+        self.update_fs_info(hardware);
+
+        Ok(())
+    }
+
+    /// Creates the directory at `path`.
+    pub fn make_directory(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        directory: Cluster,
+        path: &[u8],
+    ) -> Result<()> {
+        let (parent, name) = self.resolve_parent(hardware, directory, path)?;
+        if self.lookup(hardware, parent, name)?.is_some() {
+            return Err(Error::AlreadyExists);
+        }
+        let short_name = short_name(name)?;
+        let slot = self.free_slot(hardware, parent)?;
+
+        let cluster = self.allocate_cluster(hardware, &[])?;
+        let mut dots = [0_u8; 2 * DIRECTORY_ENTRY_SIZE];
+        dots[..DIRECTORY_ENTRY_SIZE].copy_from_slice(&raw_entry(
+            b".          ",
+            ATTRIBUTE_DIRECTORY,
+            cluster,
+            0,
+        ));
+        dots[DIRECTORY_ENTRY_SIZE..].copy_from_slice(&raw_entry(
+            b"..         ",
+            ATTRIBUTE_DIRECTORY,
+            parent,
+            0,
+        ));
+        self.write_at(hardware, cluster, 0, &dots);
+
+        let raw = raw_entry(&short_name, ATTRIBUTE_DIRECTORY, cluster, 0);
+        modify_slot(hardware, slot, |x| x.copy_from_slice(&raw));
+
+        self.update_fs_info(hardware);
+
+        Ok(())
+    }
+
+    /// Returns the number of free clusters by scanning the FAT.
+    pub fn free_cluster_count(&self, hardware: &dyn Hardware) -> u32 {
+        let mut fat_sector = FatSector::new();
+        let count = (2..self.cluster_count + 2)
+            .filter(|&x| self.fat_entry(hardware, x, &mut fat_sector) == FAT_FREE)
+            .count();
+        u32::try_from(count).unwrap_or(u32::MAX)
+    }
+
+    /// Returns the directory containing the last name in `path` and that name.
+    fn resolve_parent<'a>(
+        &self,
+        hardware: &dyn Hardware,
+        directory: Cluster,
+        path: &'a [u8],
+    ) -> Result<(Cluster, &'a [u8])> {
+        let path = &path[..path.iter().rposition(|&x| x != b'/').map_or(0, |i| i + 1)];
+        let (parent_path, name) = path
+            .iter()
+            .rposition(|&x| x == b'/')
+            .map_or((&path[..0], path), |i| (&path[..=i], &path[i + 1..]));
+
+        if name.is_empty() {
+            return Err(Error::InvalidName);
+        }
+
+        let parent = self.resolve(hardware, directory, parent_path)?;
+        if !parent.is_directory() {
+            return Err(Error::NotADirectory);
+        }
+
+        Ok((parent.cluster, name))
+    }
+
+    /// Returns the entry named `name` in `directory` ignoring case and its slot.
+    fn lookup(
+        &self,
+        hardware: &dyn Hardware,
+        directory: Cluster,
+        name: &[u8],
+    ) -> Result<Option<(Entry, Slot)>> {
+        let mut found = None;
+        self.for_each_entry_slot(hardware, directory, |entry, slot| {
+            if entry.name.eq_ignore_ascii_case(name) {
+                found = Some((*entry, slot));
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        if let Some((entry, _)) = &mut found
+            && entry.is_directory()
+            && entry.cluster == self.root_cluster
+        {
+            // note: some implementations refer to the FAT32 root by its cluster in ".."
+            entry.cluster = ROOT;
+        }
+
+        Ok(found)
+    }
+
+    fn for_each_entry_slot<F>(
+        &self,
+        hardware: &dyn Hardware,
+        directory: Cluster,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Entry, Slot) -> ControlFlow<()>,
+    {
+        self.for_each_slot(hardware, directory, |slot, raw| {
+            match raw[0] {
+                ENTRY_END => return ControlFlow::Break(()),
+                ENTRY_DELETED => return ControlFlow::Continue(()),
+                _ => {}
+            }
+            if raw[11] & ATTRIBUTE_VOLUME_ID != 0 {
+                // note: long name entries have the volume id attribute set
+                return ControlFlow::Continue(());
+            }
+            f(&Entry::parse(raw), slot)
+        })
+    }
+
+    /// Calls `f` with every slot in `directory` and its raw entry until it breaks.
+    fn for_each_slot<F>(&self, hardware: &dyn Hardware, directory: Cluster, mut f: F) -> Result<()>
+    where
+        F: FnMut(Slot, &[u8]) -> ControlFlow<()>,
+    {
+        let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        self.for_each_sector(hardware, directory, |sector_index| {
+            hardware.sdcard_read_blocking(sector_index, &mut sector);
+            for (i, raw) in sector.chunks_exact(DIRECTORY_ENTRY_SIZE).enumerate() {
+                let slot = Slot {
+                    sector: sector_index,
+                    offset: i * DIRECTORY_ENTRY_SIZE,
+                };
+                f(slot, raw)?;
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Returns a free slot in `directory` extending it with a cluster if it is full.
+    fn free_slot(&mut self, hardware: &mut dyn Hardware, directory: Cluster) -> Result<Slot> {
+        let mut free = None;
+        self.for_each_slot(hardware, directory, |slot, raw| {
+            if raw[0] == ENTRY_END || raw[0] == ENTRY_DELETED {
+                free = Some(slot);
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        if let Some(slot) = free {
+            return Ok(slot);
+        }
+
+        if directory == ROOT && self.kind == Kind::Fat16 {
+            return Err(Error::DirectoryFull);
+        }
+
+        // note: the new cluster is zeroed thus its first slot marks the end of the directory
+        let last = self.last_cluster(hardware, self.first_cluster(directory))?;
+        let cluster = self.allocate_cluster(hardware, &[])?;
+        self.set_fat(hardware, last, cluster);
+
+        Ok(Slot {
+            sector: self.cluster_first_sector(cluster),
+            offset: 0,
+        })
+    }
+
+    fn create_file(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        directory: Cluster,
+        name: &[u8],
+        data: &[u8],
+    ) -> Result<()> {
+        let short_name = short_name(name)?;
+        let size = u32::try_from(data.len()).map_err(|_| Error::Full)?;
+        let slot = self.free_slot(hardware, directory)?;
+        let cluster = self.allocate_chain(hardware, data)?;
+
+        let raw = raw_entry(&short_name, ATTRIBUTE_ARCHIVE, cluster, size);
+        modify_slot(hardware, slot, |x| x.copy_from_slice(&raw));
+
+        Ok(())
+    }
+
+    /// Writes `data` to a new cluster chain, points `entry` at `slot` to it and frees the
+    /// old chain.
+    fn replace(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        entry: &Entry,
+        slot: Slot,
+        data: &[u8],
+    ) -> Result<()> {
+        let size = u32::try_from(data.len()).map_err(|_| Error::Full)?;
+        let cluster = self.allocate_chain(hardware, data)?;
+
+        modify_slot(hardware, slot, |raw| {
+            set_raw_cluster_and_size(raw, cluster, size);
+        });
+
+        if self.is_valid_cluster(entry.cluster) {
+            self.free_chain(hardware, entry.cluster)?;
+        }
+
+        Ok(())
+    }
+
+    /// Appends `data` to the non-empty file `entry` at `slot` by filling its last cluster
+    /// and linking a new chain for the rest.
+    fn extend(
+        &mut self,
+        hardware: &mut dyn Hardware,
+        entry: &Entry,
+        slot: Slot,
+        data: &[u8],
+    ) -> Result<()> {
+        let size = entry.size as usize + data.len();
+        let size = u32::try_from(size).map_err(|_| Error::Full)?;
+
+        let bytes_per_cluster = self.bytes_per_cluster();
+        let last_index = (entry.size as usize - 1) / bytes_per_cluster;
+        let used = entry.size as usize - last_index * bytes_per_cluster;
+
+        let mut last = entry.cluster;
+        for _ in 0..last_index {
+            if !self.is_valid_cluster(last) {
+                return Err(Error::Corrupt);
+            }
+            last = self.next_cluster(hardware, last).ok_or(Error::Corrupt)?;
+        }
+        if !self.is_valid_cluster(last) {
+            return Err(Error::Corrupt);
+        }
+
+        // note: beyond the current size thus not visible until the entry is updated
+        let (head, tail) = data.split_at(min(bytes_per_cluster - used, data.len()));
+        self.write_at(hardware, last, used, head);
+
+        let mut unlinked = None;
+        if !tail.is_empty() {
+            unlinked = self.next_cluster(hardware, last);
+            let first = self.allocate_chain(hardware, tail)?;
+            self.set_fat(hardware, last, first);
+        }
+
+        modify_slot(hardware, slot, |raw| {
+            set_raw_cluster_and_size(raw, entry.cluster, size);
+        });
+
+        if let Some(cluster) = unlinked {
+            // note: clusters past the size left by an earlier interrupted write
+            self.free_chain(hardware, cluster)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `data` into `cluster` starting at `offset` keeping the rest of the cluster.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    fn write_at(&self, hardware: &mut dyn Hardware, cluster: Cluster, offset: usize, data: &[u8]) {
+        let mut sector_index =
+            self.cluster_first_sector(cluster) + (offset / SDCARD_SECTOR_SIZE_BYTES) as u32;
+        let mut offset = offset % SDCARD_SECTOR_SIZE_BYTES;
+        let mut data = data;
+        let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+
+        while !data.is_empty() {
+            let len = min(SDCARD_SECTOR_SIZE_BYTES - offset, data.len());
+            hardware.sdcard_read_blocking(sector_index, &mut sector);
+            sector[offset..offset + len].copy_from_slice(&data[..len]);
+            hardware.sdcard_write_blocking(sector_index, &sector);
+            data = &data[len..];
+            offset = 0;
+            sector_index += 1;
+        }
+    }
+
+    /// Writes `data` to a new cluster chain and returns its first cluster or 0 if `data` is
+    /// empty.
+    fn allocate_chain(&mut self, hardware: &mut dyn Hardware, data: &[u8]) -> Result<Cluster> {
+        let mut first = 0;
+        let mut last = 0;
+        for chunk in data.chunks(self.bytes_per_cluster()) {
+            let cluster = match self.allocate_cluster(hardware, chunk) {
+                Ok(cluster) => cluster,
+                Err(e) => {
+                    if first != 0 {
+                        self.free_chain(hardware, first)?;
+                    }
+                    return Err(e);
+                }
+            };
+            if first == 0 {
+                first = cluster;
+            } else {
+                self.set_fat(hardware, last, cluster);
+            }
+            last = cluster;
+        }
+        Ok(first)
+    }
+
+    /// Writes `data` zero padded to a free cluster and marks it as end of chain.
+    fn allocate_cluster(&mut self, hardware: &mut dyn Hardware, data: &[u8]) -> Result<Cluster> {
+        let cluster = self.find_free_cluster(hardware)?;
+
+        let first_sector = self.cluster_first_sector(cluster);
+        let chunks = data.chunks(SDCARD_SECTOR_SIZE_BYTES).chain(repeat(&[][..]));
+        for (sector_index, chunk) in
+            (first_sector..first_sector + self.sectors_per_cluster).zip(chunks)
+        {
+            let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+            sector[..chunk.len()].copy_from_slice(chunk);
+            hardware.sdcard_write_blocking(sector_index, &sector);
+        }
+
+        self.set_fat(hardware, cluster, FAT_END_OF_CHAIN_MARK);
+        self.next_free = cluster + 1;
+        self.allocated += 1;
+
+        Ok(cluster)
+    }
+
+    fn find_free_cluster(&mut self, hardware: &dyn Hardware) -> Result<Cluster> {
+        if !self.is_valid_cluster(self.next_free) {
+            self.next_free = self
+                .read_fs_info(hardware)
+                .map(|x| u32_at(&x, FS_INFO_NEXT_FREE))
+                .filter(|&x| self.is_valid_cluster(x))
+                .unwrap_or(2);
+        }
+
+        let start = self.next_free;
+        let end = self.cluster_count + 2;
+        let mut fat_sector = FatSector::new();
+        (start..end)
+            .chain(2..start)
+            .find(|&x| self.fat_entry(hardware, x, &mut fat_sector) == FAT_FREE)
+            .ok_or(Error::Full)
+    }
+
+    fn free_chain(&mut self, hardware: &mut dyn Hardware, first: Cluster) -> Result<()> {
+        let mut cluster = first;
+
+        // note: bounded to not loop forever on a corrupt chain
+        for _ in 0..self.cluster_count {
+            if !self.is_valid_cluster(cluster) {
+                return Err(Error::Corrupt);
+            }
+            let next = self.next_cluster(hardware, cluster);
+            self.set_fat(hardware, cluster, FAT_FREE);
+            self.freed += 1;
+            match next {
+                Some(next) => cluster = next,
+                None => return Ok(()),
+            }
+        }
+
+        Err(Error::Corrupt)
+    }
+
+    fn last_cluster(&self, hardware: &dyn Hardware, first: Cluster) -> Result<Cluster> {
+        let mut cluster = first;
+        for _ in 0..self.cluster_count {
+            if !self.is_valid_cluster(cluster) {
+                return Err(Error::Corrupt);
+            }
+            match self.next_cluster(hardware, cluster) {
+                Some(next) => cluster = next,
+                None => return Ok(cluster),
+            }
+        }
+        Err(Error::Corrupt)
+    }
+
+    /// Updates the free cluster count and next free hint of FAT32 with the clusters
+    /// allocated and freed since last update.
+    fn update_fs_info(&mut self, hardware: &mut dyn Hardware) {
+        let (allocated, freed) = (self.allocated, self.freed);
+        self.allocated = 0;
+        self.freed = 0;
+
+        let Some(sector_index) = self.fs_info_sector else {
+            return;
+        };
+        let Some(mut sector) = self.read_fs_info(hardware) else {
+            return;
+        };
+
+        // note: the count may be unknown (0xffff_ffff) thus recounted
+        let free = u32_at(&sector, FS_INFO_FREE_COUNT)
+            .checked_add(freed)
+            .and_then(|x| x.checked_sub(allocated))
+            .filter(|&x| x <= self.cluster_count)
+            .unwrap_or_else(|| self.free_cluster_count(hardware));
+
+        sector[FS_INFO_FREE_COUNT..FS_INFO_FREE_COUNT + 4].copy_from_slice(&free.to_le_bytes());
+        sector[FS_INFO_NEXT_FREE..FS_INFO_NEXT_FREE + 4]
+            .copy_from_slice(&self.next_free.to_le_bytes());
+        hardware.sdcard_write_blocking(sector_index, &sector);
+    }
+
+    /// Returns the FAT32 information sector if it has valid signatures.
+    fn read_fs_info(&self, hardware: &dyn Hardware) -> Option<[u8; SDCARD_SECTOR_SIZE_BYTES]> {
+        let sector_index = self.fs_info_sector?;
+        let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        hardware.sdcard_read_blocking(sector_index, &mut sector);
+        (u32_at(&sector, 0) == FS_INFO_LEAD_SIGNATURE
+            && u32_at(&sector, 484) == FS_INFO_STRUCT_SIGNATURE)
+            .then_some(sector)
+    }
+
+    /// Calls `f` with each sector of the cluster chain starting at `first` or of the root
+    /// directory until it breaks.
+    fn for_each_sector<F>(&self, hardware: &dyn Hardware, first: Cluster, mut f: F) -> Result<()>
+    where
+        F: FnMut(u32) -> ControlFlow<()>,
+    {
+        if first == ROOT && self.kind == Kind::Fat16 {
+            for sector in self.root_first_sector..self.root_first_sector + self.root_sector_count {
+                if f(sector).is_break() {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let mut cluster = self.first_cluster(first);
+
+        // note: bounded to not loop forever on a corrupt chain
+        for _ in 0..self.cluster_count {
+            if !self.is_valid_cluster(cluster) {
+                return Err(Error::Corrupt);
+            }
+            let first_sector = self.cluster_first_sector(cluster);
+            for sector in first_sector..first_sector + self.sectors_per_cluster {
+                if f(sector).is_break() {
+                    return Ok(());
+                }
+            }
+            match self.next_cluster(hardware, cluster) {
+                Some(next) => cluster = next,
+                None => return Ok(()),
+            }
+        }
+
+        Err(Error::Corrupt)
+    }
+
+    /// Returns the cluster following `cluster` in the FAT or `None` at end of chain.
+    fn next_cluster(&self, hardware: &dyn Hardware, cluster: Cluster) -> Option<Cluster> {
+        let next = self.fat_entry(hardware, cluster, &mut FatSector::new());
+        let end_of_chain = match self.kind {
+            Kind::Fat16 => FAT16_END_OF_CHAIN,
+            Kind::Fat32 => FAT32_END_OF_CHAIN,
+        };
+
+        // note: free and bad clusters are caught as invalid by the caller
+        (next < end_of_chain).then_some(next)
+    }
+
+    /// Returns the FAT entry of `cluster` reading its sector unless it is `fat_sector`.
+    fn fat_entry(
+        &self,
+        hardware: &dyn Hardware,
+        cluster: Cluster,
+        fat_sector: &mut FatSector,
+    ) -> u32 {
+        let (index, offset) = self.fat_position(cluster);
+        if fat_sector.index != index {
+            hardware.sdcard_read_blocking(self.fat_first_sector + index, &mut fat_sector.data);
+            fat_sector.index = index;
+        }
+        let b = &fat_sector.data[offset..];
+        match self.kind {
+            Kind::Fat16 => u32::from(u16::from_le_bytes([b[0], b[1]])),
+            Kind::Fat32 => u32_at(b, 0) & FAT32_CLUSTER_MASK,
+        }
+    }
+
+    /// Sets the entry of `cluster` to `value` in every FAT.
+    #[expect(clippy::cast_possible_truncation, reason = "FAT16 entries are 16 bits")]
+    fn set_fat(&self, hardware: &mut dyn Hardware, cluster: Cluster, value: u32) {
+        let (index, offset) = self.fat_position(cluster);
+        let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        for fat in 0..self.fat_count {
+            let sector_index = self.fat_first_sector + fat * self.fat_size + index;
+            hardware.sdcard_read_blocking(sector_index, &mut sector);
+            match self.kind {
+                Kind::Fat16 => {
+                    sector[offset..offset + 2].copy_from_slice(&(value as u16).to_le_bytes());
+                }
+                Kind::Fat32 => {
+                    // note: the upper 4 bits are reserved and kept
+                    let reserved = u32_at(&sector, offset) & !FAT32_CLUSTER_MASK;
+                    let value = reserved | (value & FAT32_CLUSTER_MASK);
+                    sector[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+                }
+            }
+            hardware.sdcard_write_blocking(sector_index, &sector);
+        }
+    }
+
+    /// Returns the sector within the FAT and the offset in it of the entry of `cluster`.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    const fn fat_position(&self, cluster: Cluster) -> (u32, usize) {
+        let entry_size = match self.kind {
+            Kind::Fat16 => 2,
+            Kind::Fat32 => 4,
+        };
+        let offset = cluster as usize * entry_size;
+        (
+            (offset / SDCARD_SECTOR_SIZE_BYTES) as u32,
+            offset % SDCARD_SECTOR_SIZE_BYTES,
+        )
+    }
+
+    const fn first_cluster(&self, directory: Cluster) -> Cluster {
+        if directory == ROOT {
+            self.root_cluster
+        } else {
+            directory
+        }
+    }
+
+    const fn cluster_first_sector(&self, cluster: Cluster) -> u32 {
+        self.data_first_sector + (cluster - 2) * self.sectors_per_cluster
+    }
+
+    const fn bytes_per_cluster(&self) -> usize {
+        self.sectors_per_cluster as usize * SDCARD_SECTOR_SIZE_BYTES
+    }
+
+    const fn is_valid_cluster(&self, cluster: Cluster) -> bool {
+        cluster >= 2 && cluster - 2 < self.cluster_count
+    }
+}
+
+/// Returns `name` such as "log.txt" in directory entry format "LOG     TXT".
+fn short_name(name: &[u8]) -> Result<[u8; 11]> {
+    let (base, extension) = name
+        .iter()
+        .position(|&x| x == b'.')
+        .map_or((name, &name[..0]), |i| (&name[..i], &name[i + 1..]));
+
+    let valid = |x: &u8| x.is_ascii_alphanumeric() || NAME_SPECIAL_CHARACTERS.contains(x);
+    if base.is_empty()
+        || base.len() > 8
+        || extension.len() > 3
+        || !base.iter().chain(extension).all(valid)
+    {
+        return Err(Error::InvalidName);
+    }
+
+    let mut short = [b' '; 11];
+    short[..base.len()].copy_from_slice(base);
+    short[8..8 + extension.len()].copy_from_slice(extension);
+    short.make_ascii_uppercase();
+    Ok(short)
+}
+
+/// Calls `f` with the raw entry at `slot` and writes it back.
+fn modify_slot<F>(hardware: &mut dyn Hardware, slot: Slot, f: F)
+where
+    F: FnOnce(&mut [u8]),
+{
+    let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    hardware.sdcard_read_blocking(slot.sector, &mut sector);
+    f(&mut sector[slot.offset..slot.offset + DIRECTORY_ENTRY_SIZE]);
+    hardware.sdcard_write_blocking(slot.sector, &sector);
+}
+
+fn raw_entry(
+    name: &[u8; 11],
+    attributes: u8,
+    cluster: Cluster,
+    size: u32,
+) -> [u8; DIRECTORY_ENTRY_SIZE] {
+    let mut raw = [0_u8; DIRECTORY_ENTRY_SIZE];
+    raw[..11].copy_from_slice(name);
+    raw[11] = attributes;
+    for offset in [16, 18, 24] {
+        // note: creation, access and modification dates
+        raw[offset..offset + 2].copy_from_slice(&DATE_1980_01_01.to_le_bytes());
+    }
+    set_raw_cluster_and_size(&mut raw, cluster, size);
+    raw
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "cluster is split in two 16 bit halves"
+)]
+fn set_raw_cluster_and_size(raw: &mut [u8], cluster: Cluster, size: u32) {
+    raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    raw[28..32].copy_from_slice(&size.to_le_bytes());
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+}
+
+fn trim_end_spaces(bytes: &[u8]) -> &[u8] {
+    let len = bytes.iter().rposition(|&x| x != b' ').map_or(0, |i| i + 1);
+    &bytes[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::fat_image::FatImage;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn ok<T>(result: Result<T>) -> T {
+        let Ok(value) = result else {
+            panic!("file system error");
+        };
+        value
+    }
+
+    fn names(image: &FatImage, directory: Cluster) -> Vec<Vec<u8>> {
+        let volume = ok(mount(&image.hardware));
+        let mut names = Vec::new();
+        ok(volume.for_each_entry(&image.hardware, directory, |entry| {
+            names.push(entry.name.to_vec());
+            ControlFlow::Continue(())
+        }));
+        names
+    }
+
+    fn content(image: &FatImage, path: &[u8]) -> Result<Vec<u8>> {
+        let volume = mount(&image.hardware)?;
+        let entry = volume.resolve(&image.hardware, ROOT, path)?;
+        let mut content = Vec::new();
+        volume.read(&image.hardware, &entry, |x| content.extend_from_slice(x))?;
+        Ok(content)
+    }
+
+    fn populated(mut image: FatImage) -> FatImage {
+        image.file(ROOT, b"README.TXT", b"hello\n");
+        let docs = image.directory(ROOT, b"DOCS");
+        let big: Vec<u8> = (b'a'..=b'z').cycle().take(1300).collect();
+        image.file(docs, b"BIG.DAT", &big);
+        image.file(docs, b"EMPTY", b"");
+        image
+    }
+
+    #[test]
+    fn lists_directories() {
+        for image in [populated(FatImage::fat16()), populated(FatImage::fat32())] {
+            assert_eq!(names(&image, ROOT), [b"README.TXT".as_slice(), b"DOCS"]);
+            let volume = ok(mount(&image.hardware));
+            let docs = ok(volume.resolve(&image.hardware, ROOT, b"docs"));
+            assert!(docs.is_directory());
+            assert_eq!(
+                names(&image, docs.cluster),
+                [b".".as_slice(), b"..", b"BIG.DAT", b"EMPTY"]
+            );
+        }
+    }
+
+    #[test]
+    fn reads_files_across_clusters() {
+        for image in [populated(FatImage::fat16()), populated(FatImage::fat32())] {
+            assert_eq!(ok(content(&image, b"readme.txt")), b"hello\n");
+            let big = ok(content(&image, b"/DOCS/BIG.DAT"));
+            assert_eq!(big.len(), 1300);
+            assert!(big.iter().zip((b'a'..=b'z').cycle()).all(|(&a, b)| a == b));
+            assert!(ok(content(&image, b"docs/empty")).is_empty());
+        }
+    }
+
+    #[test]
+    fn resolves_relative_paths() {
+        let image = populated(FatImage::fat32());
+        let volume = ok(mount(&image.hardware));
+        let docs = ok(volume.resolve(&image.hardware, ROOT, b"DOCS/"));
+        let entry = ok(volume.resolve(&image.hardware, docs.cluster, b"./../docs/./big.dat"));
+        assert_eq!(entry.size, 1300);
+        let root = ok(volume.resolve(&image.hardware, docs.cluster, b".."));
+        assert_eq!(root.cluster, ROOT);
+        let root = ok(volume.resolve(&image.hardware, docs.cluster, b"/"));
+        assert_eq!(root.cluster, ROOT);
+        assert!(matches!(
+            volume.resolve(&image.hardware, ROOT, b"missing"),
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            volume.resolve(&image.hardware, ROOT, b"readme.txt/x"),
+            Err(Error::NotADirectory)
+        ));
+        assert!(matches!(content(&image, b"docs"), Err(Error::IsADirectory)));
+    }
+
+    #[test]
+    fn skips_deleted_long_name_and_volume_entries() {
+        let mut image = FatImage::fat16();
+        let mut long_name = [0_u8; 32];
+        long_name[0] = 0x41;
+        long_name[11] = 0x0f;
+        image.raw_entry(ROOT, &long_name);
+        let mut volume_label = [b' '; 32];
+        volume_label[11] = ATTRIBUTE_VOLUME_ID;
+        image.raw_entry(ROOT, &volume_label);
+        let mut deleted = [b' '; 32];
+        deleted[0] = ENTRY_DELETED;
+        deleted[11] = 0;
+        image.raw_entry(ROOT, &deleted);
+        image.file(ROOT, b"A.TXT", b"a");
+        assert_eq!(names(&image, ROOT), [b"A.TXT"]);
+    }
+
+    #[test]
+    fn rejects_missing_file_system() {
+        let image = FatImage::fat16();
+        let mut hardware = image.hardware;
+        hardware.sector_mut(0)[510] = 0;
+        assert!(matches!(mount(&hardware), Err(Error::NoFileSystem)));
+        hardware.sdcard_status = 0;
+        assert!(matches!(mount(&hardware), Err(Error::CardNotReady)));
+    }
+
+    #[test]
+    fn detects_corrupt_cluster_chains() {
+        let mut image = FatImage::fat32();
+        let cluster = image.file(ROOT, b"SHORT", &[b'x'; 2000]);
+        image.set_fat(cluster + 2, FAT32_END_OF_CHAIN);
+        assert!(matches!(content(&image, b"short"), Err(Error::Corrupt)));
+
+        // note: a full directory that links to itself has no end
+        let directory = image.directory(ROOT, b"LOOP");
+        for name in b"0123456789abcd".chunks(1) {
+            image.file(directory, name, b"");
+        }
+        image.set_fat(directory, directory);
+        let volume = ok(mount(&image.hardware));
+        assert!(matches!(
+            volume.find(&image.hardware, directory, b"missing"),
+            Err(Error::Corrupt)
+        ));
+    }
+
+    #[test]
+    fn writes_and_replaces_files() {
+        for mut image in [FatImage::fat16(), FatImage::fat32()] {
+            let mut volume = ok(mount(&image.hardware));
+            let free = volume.free_cluster_count(&image.hardware);
+            let big: Vec<u8> = (b'a'..=b'z').cycle().take(1300).collect();
+
+            ok(volume.write(&mut image.hardware, ROOT, b"log.txt", &big));
+            assert_eq!(ok(content(&image, b"LOG.TXT")), big);
+            assert_eq!(volume.free_cluster_count(&image.hardware), free - 3);
+
+            ok(volume.write(&mut image.hardware, ROOT, b"log.txt", b"short"));
+            assert_eq!(ok(content(&image, b"log.txt")), b"short");
+            assert_eq!(volume.free_cluster_count(&image.hardware), free - 1);
+
+            ok(volume.write(&mut image.hardware, ROOT, b"log.txt", b""));
+            assert!(ok(content(&image, b"log.txt")).is_empty());
+            assert_eq!(volume.free_cluster_count(&image.hardware), free);
+
+            assert_eq!(names(&image, ROOT), [b"LOG.TXT"]);
+            assert!(image.fats_match());
+        }
+    }
+
+    #[test]
+    fn appends_across_clusters() {
+        for mut image in [FatImage::fat16(), FatImage::fat32()] {
+            let mut volume = ok(mount(&image.hardware));
+            ok(volume.append(&mut image.hardware, ROOT, b"log", &[b'a'; 500]));
+            ok(volume.append(&mut image.hardware, ROOT, b"log", &[b'b'; 100]));
+            ok(volume.append(&mut image.hardware, ROOT, b"log", &[b'c'; 424]));
+            ok(volume.append(&mut image.hardware, ROOT, b"log", b"d"));
+
+            let log = ok(content(&image, b"log"));
+            assert_eq!(log.len(), 1025);
+            assert!(log[..500].iter().all(|&x| x == b'a'));
+            assert!(log[500..600].iter().all(|&x| x == b'b'));
+            assert!(log[600..1024].iter().all(|&x| x == b'c'));
+            assert_eq!(log[1024], b'd');
+            assert!(image.fats_match());
+        }
+    }
+
+    #[test]
+    fn makes_and_removes_directories() {
+        for mut image in [FatImage::fat16(), FatImage::fat32()] {
+            let mut volume = ok(mount(&image.hardware));
+            let free = volume.free_cluster_count(&image.hardware);
+
+            ok(volume.make_directory(&mut image.hardware, ROOT, b"docs"));
+            ok(volume.write(&mut image.hardware, ROOT, b"/docs/a.txt", b"a"));
+            let docs = ok(volume.resolve(&image.hardware, ROOT, b"docs"));
+            ok(volume.make_directory(&mut image.hardware, docs.cluster, b"sub/"));
+            assert_eq!(
+                names(&image, docs.cluster),
+                [b".".as_slice(), b"..", b"A.TXT", b"SUB"]
+            );
+            let root = ok(volume.resolve(&image.hardware, docs.cluster, b"sub/../.."));
+            assert_eq!(root.cluster, ROOT);
+
+            assert!(matches!(
+                volume.remove(&mut image.hardware, ROOT, b"docs"),
+                Err(Error::DirectoryNotEmpty)
+            ));
+            ok(volume.remove(&mut image.hardware, docs.cluster, b"a.txt"));
+            ok(volume.remove(&mut image.hardware, ROOT, b"docs/sub"));
+            ok(volume.remove(&mut image.hardware, ROOT, b"docs"));
+
+            assert!(names(&image, ROOT).is_empty());
+            assert_eq!(volume.free_cluster_count(&image.hardware), free);
+            assert!(image.fats_match());
+        }
+    }
+
+    #[test]
+    fn grows_full_directory_and_reuses_deleted_entries() {
+        let mut image = FatImage::fat32();
+        let mut volume = ok(mount(&image.hardware));
+        ok(volume.make_directory(&mut image.hardware, ROOT, b"d"));
+        let directory = ok(volume.resolve(&image.hardware, ROOT, b"d")).cluster;
+
+        for name in b"0123456789abcdefghij".chunks(1) {
+            ok(volume.write(&mut image.hardware, directory, name, name));
+        }
+        assert_eq!(names(&image, directory).len(), 22);
+        assert_eq!(ok(content(&image, b"d/j")), b"j");
+
+        ok(volume.remove(&mut image.hardware, directory, b"0"));
+        ok(volume.write(&mut image.hardware, directory, b"k", b"k"));
+        assert_eq!(names(&image, directory)[2], b"K");
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        let mut image = populated(FatImage::fat16());
+        let mut volume = ok(mount(&image.hardware));
+        let hw = &mut image.hardware;
+
+        for name in [
+            b"toolongname".as_slice(),
+            b"a.text",
+            b"a.b.c",
+            b"a b",
+            b".x",
+        ] {
+            assert!(matches!(
+                volume.write(hw, ROOT, name, b"x"),
+                Err(Error::InvalidName)
+            ));
+        }
+        assert!(matches!(
+            volume.make_directory(hw, ROOT, b"docs"),
+            Err(Error::AlreadyExists)
+        ));
+        assert!(matches!(
+            volume.write(hw, ROOT, b"docs", b"x"),
+            Err(Error::IsADirectory)
+        ));
+        assert!(matches!(
+            volume.append(hw, ROOT, b"readme.txt/x", b"x"),
+            Err(Error::NotADirectory)
+        ));
+        assert!(matches!(
+            volume.remove(hw, ROOT, b"/"),
+            Err(Error::InvalidName)
+        ));
+        assert!(matches!(
+            volume.remove(hw, ROOT, b"docs/.."),
+            Err(Error::InvalidName)
+        ));
+        assert!(matches!(
+            volume.remove(hw, ROOT, b"missing"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn keeps_free_cluster_count_in_fs_info() {
+        let mut image = FatImage::fat32();
+        let mut volume = ok(mount(&image.hardware));
+        assert_eq!(image.fs_info_free_count(), u32::MAX);
+
+        ok(volume.write(&mut image.hardware, ROOT, b"a", &[b'a'; 1500]));
+        let free = volume.free_cluster_count(&image.hardware);
+        assert_eq!(image.fs_info_free_count(), free);
+
+        ok(volume.make_directory(&mut image.hardware, ROOT, b"d"));
+        ok(volume.append(&mut image.hardware, ROOT, b"a", &[b'a'; 600]));
+        ok(volume.remove(&mut image.hardware, ROOT, b"a"));
+        assert_eq!(image.fs_info_free_count(), free + 3 - 1);
+        assert_eq!(
+            image.fs_info_free_count(),
+            volume.free_cluster_count(&image.hardware)
+        );
+    }
+
+    #[test]
+    fn full_card_leaves_no_lost_clusters() {
+        let mut image = FatImage::fat16();
+        let mut volume = ok(mount(&image.hardware));
+        let free = volume.free_cluster_count(&image.hardware) as usize;
+
+        let data = vec![b'x'; (free + 1) * 512];
+        assert!(matches!(
+            volume.write(&mut image.hardware, ROOT, b"big", &data),
+            Err(Error::Full)
+        ));
+        assert_eq!(volume.free_cluster_count(&image.hardware) as usize, free);
+
+        ok(volume.write(&mut image.hardware, ROOT, b"big", &data[..free * 512]));
+        assert_eq!(volume.free_cluster_count(&image.hardware), 0);
+    }
+
+    #[test]
+    fn reads_file_a_sector_at_a_time() {
+        let image = populated(FatImage::fat32());
+        let volume = ok(mount(&image.hardware));
+        let entry = ok(volume.resolve(&image.hardware, ROOT, b"docs/big.dat"));
+        let mut cursor = ok(volume.open(&entry));
+        let mut buffer = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        let mut content = Vec::new();
+        loop {
+            let len = ok(volume.read_next(&image.hardware, &mut cursor, &mut buffer));
+            if len == 0 {
+                break;
+            }
+            content.extend_from_slice(&buffer[..len]);
+        }
+        assert_eq!(content, ok(self::content(&image, b"docs/big.dat")));
+
+        let docs = ok(volume.resolve(&image.hardware, ROOT, b"docs"));
+        assert!(matches!(volume.open(&docs), Err(Error::IsADirectory)));
+    }
+}
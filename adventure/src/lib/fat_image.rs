@@ -0,0 +1,246 @@
+//
+// FAT16 and FAT32 images built in memory by tests
+//
+#![allow(clippy::cast_possible_truncation, reason = "test images are small")]
+
+use super::api_memory::HardwareMemory;
+use super::fat::{Cluster, ROOT};
+use super::mbr;
+
+const PARTITION_FIRST_SECTOR: u32 = 64;
+const SECTOR_SIZE: usize = 512;
+const END_OF_CHAIN: u32 = 0x0fff_ffff;
+const ATTRIBUTE_DIRECTORY: u8 = 0x10;
+const ATTRIBUTE_ARCHIVE: u8 = 0x20;
+
+/// SD card with an MBR and one FAT partition with one sector per cluster.
+/// note: clusters are allocated with a gap in between so that chains must be followed
+pub struct FatImage {
+    pub hardware: HardwareMemory,
+    fat32: bool,
+    partition_first_sector: u32,
+    fat_first_sector: u32,
+    fat_size: u32,
+    root_first_sector: u32,
+    root_sector_count: u32,
+    data_first_sector: u32,
+    next_free: Cluster,
+}
+
+impl FatImage {
+    pub fn fat16() -> Self {
+        Self::format(false, PARTITION_FIRST_SECTOR)
+    }
+
+    pub fn fat32() -> Self {
+        Self::format(true, PARTITION_FIRST_SECTOR)
+    }
+
+    /// FAT16 partition that starts at `partition_first_sector`.
+    pub fn fat16_at(partition_first_sector: u32) -> Self {
+        Self::format(false, partition_first_sector)
+    }
+
+    fn format(fat32: bool, partition_first_sector: u32) -> Self {
+        // note: FAT16 needs at least 4085 clusters
+        let (total, reserved, fat_size, root_entries): (u32, u16, u32, u16) = if fat32 {
+            (1200, 32, 10, 0)
+        } else {
+            (4400, 4, 17, 512)
+        };
+
+        let mut hardware = HardwareMemory::new((partition_first_sector + total) as usize);
+
+        let sector = hardware.sector_mut(0);
+        let entry = &mut sector[446..462];
+        entry[4] = if fat32 {
+            mbr::KIND_FAT32_LBA
+        } else {
+            mbr::KIND_FAT16_LBA
+        };
+        entry[8..12].copy_from_slice(&partition_first_sector.to_le_bytes());
+        entry[12..16].copy_from_slice(&total.to_le_bytes());
+        sector[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+        let bs = hardware.sector_mut(partition_first_sector);
+        bs[0..3].copy_from_slice(&[0xeb, 0x3c, 0x90]);
+        bs[3..11].copy_from_slice(b"MSWIN4.1");
+        bs[11..13].copy_from_slice(&512_u16.to_le_bytes());
+        bs[13] = 1;
+        bs[14..16].copy_from_slice(&reserved.to_le_bytes());
+        bs[16] = 2;
+        bs[17..19].copy_from_slice(&root_entries.to_le_bytes());
+        bs[21] = 0xf8;
+        bs[32..36].copy_from_slice(&total.to_le_bytes());
+        if fat32 {
+            bs[36..40].copy_from_slice(&fat_size.to_le_bytes());
+            bs[44..48].copy_from_slice(&2_u32.to_le_bytes());
+            bs[48..50].copy_from_slice(&1_u16.to_le_bytes());
+        } else {
+            bs[22..24].copy_from_slice(&(fat_size as u16).to_le_bytes());
+        }
+        bs[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+        if fat32 {
+            // note: free cluster count and next free cluster unknown
+            let fs_info = hardware.sector_mut(partition_first_sector + 1);
+            fs_info[0..4].copy_from_slice(&0x4161_5252_u32.to_le_bytes());
+            fs_info[484..488].copy_from_slice(&0x6141_7272_u32.to_le_bytes());
+            fs_info[488..496].fill(0xff);
+            fs_info[508..512].copy_from_slice(&0xaa55_0000_u32.to_le_bytes());
+        }
+
+        let fat_first_sector = partition_first_sector + u32::from(reserved);
+        let root_first_sector = fat_first_sector + 2 * fat_size;
+        let root_sector_count = u32::from(root_entries) * 32 / SECTOR_SIZE as u32;
+
+        let mut image = Self {
+            hardware,
+            fat32,
+            partition_first_sector,
+            fat_first_sector,
+            fat_size,
+            root_first_sector,
+            root_sector_count,
+            data_first_sector: root_first_sector + root_sector_count,
+            next_free: 2,
+        };
+
+        image.set_fat(0, 0x0fff_fff8);
+        image.set_fat(1, END_OF_CHAIN);
+        if fat32 {
+            // note: root directory in cluster 2
+            image.allocate(1);
+        }
+
+        image
+    }
+
+    /// Sets the FAT entry of `cluster` to `value` in both FATs.
+    pub fn set_fat(&mut self, cluster: Cluster, value: u32) {
+        let entry_size = if self.fat32 { 4 } else { 2 };
+        let offset = cluster as usize * entry_size;
+        for fat in 0..2 {
+            let sector = self.hardware.sector_mut(
+                self.fat_first_sector + fat * self.fat_size + (offset / SECTOR_SIZE) as u32,
+            );
+            let bytes = value.to_le_bytes();
+            let i = offset % SECTOR_SIZE;
+            sector[i..i + entry_size].copy_from_slice(&bytes[..entry_size]);
+        }
+    }
+
+    /// Returns true if both FATs are equal.
+    pub fn fats_match(&self) -> bool {
+        (0..self.fat_size).all(|i| {
+            self.hardware.sector(self.fat_first_sector + i)
+                == self
+                    .hardware
+                    .sector(self.fat_first_sector + self.fat_size + i)
+        })
+    }
+
+    /// Returns the FAT32 free cluster count in the information sector.
+    pub fn fs_info_free_count(&self) -> u32 {
+        let sector = self.hardware.sector(self.partition_first_sector + 1);
+        u32::from_le_bytes([sector[488], sector[489], sector[490], sector[491]])
+    }
+
+    /// Adds a directory to `parent` and returns its cluster.
+    pub fn directory(&mut self, parent: Cluster, name: &[u8]) -> Cluster {
+        let cluster = self.allocate(1);
+        self.entry(cluster, b".", ATTRIBUTE_DIRECTORY, cluster, 0);
+        self.entry(cluster, b"..", ATTRIBUTE_DIRECTORY, parent, 0);
+        self.entry(parent, name, ATTRIBUTE_DIRECTORY, cluster, 0);
+        cluster
+    }
+
+    /// Adds a file to `parent` and returns its first cluster.
+    pub fn file(&mut self, parent: Cluster, name: &[u8], data: &[u8]) -> Cluster {
+        let first = self.allocate(data.len().div_ceil(SECTOR_SIZE));
+        for (i, chunk) in data.chunks(SECTOR_SIZE).enumerate() {
+            let sector = self.cluster_sector(first + 2 * i as u32);
+            self.hardware.sector_mut(sector)[..chunk.len()].copy_from_slice(chunk);
+        }
+        self.entry(parent, name, ATTRIBUTE_ARCHIVE, first, data.len() as u32);
+        first
+    }
+
+    /// Writes `raw` into the first free entry of `directory`.
+    ///
+    /// # Panics
+    ///
+    /// If `directory` is full.
+    pub fn raw_entry(&mut self, directory: Cluster, raw: &[u8; 32]) {
+        let sectors = if directory == ROOT && !self.fat32 {
+            self.root_first_sector..self.root_first_sector + self.root_sector_count
+        } else {
+            let sector = self.cluster_sector(if directory == ROOT { 2 } else { directory });
+            sector..sector + 1
+        };
+
+        for sector in sectors {
+            let data = self.hardware.sector_mut(sector);
+            if let Some(slot) = data.chunks_exact_mut(32).find(|x| x[0] == 0) {
+                slot.copy_from_slice(raw);
+                return;
+            }
+        }
+
+        panic!("directory full");
+    }
+
+    fn entry(
+        &mut self,
+        directory: Cluster,
+        name: &[u8],
+        attributes: u8,
+        cluster: Cluster,
+        size: u32,
+    ) {
+        let mut raw = [0_u8; 32];
+        raw[..11].copy_from_slice(&short_name(name));
+        raw[11] = attributes;
+        raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+        raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+        raw[28..32].copy_from_slice(&size.to_le_bytes());
+        self.raw_entry(directory, &raw);
+    }
+
+    /// Allocates a chain of `count` clusters and returns the first or 0 if `count` is 0.
+    fn allocate(&mut self, count: usize) -> Cluster {
+        let first = if count == 0 { 0 } else { self.next_free };
+        for i in 0..count {
+            let cluster = self.next_free;
+            self.next_free += 2;
+            let next = if i + 1 == count {
+                END_OF_CHAIN
+            } else {
+                self.next_free
+            };
+            self.set_fat(cluster, next);
+        }
+        first
+    }
+
+    const fn cluster_sector(&self, cluster: Cluster) -> u32 {
+        self.data_first_sector + cluster - 2
+    }
+}
+
+/// Returns `name` such as "readme.txt" as "README  TXT".
+fn short_name(name: &[u8]) -> [u8; 11] {
+    let mut short = [b' '; 11];
+    if name == b"." || name == b".." {
+        short[..name.len()].copy_from_slice(name);
+        return short;
+    }
+    let (base, extension) = name
+        .iter()
+        .position(|&x| x == b'.')
+        .map_or((name, &[][..]), |i| (&name[..i], &name[i + 1..]));
+    short[..base.len()].copy_from_slice(base);
+    short[8..8 + extension.len()].copy_from_slice(extension);
+    short.make_ascii_uppercase();
+    short
+}
@@ -81,3 +81,25 @@ impl<const SIZE: usize> PartialEq<Self> for FixedSizeString<SIZE> {
 }
 
 impl<const N: usize> Eq for FixedSizeString<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_parts_truncates_to_size() {
+        let s = FixedSizeString::<8>::from_parts(&[b"hello", b" ", b"world"]);
+        assert_eq!(&*s, b"hello wo");
+    }
+
+    #[test]
+    fn append_and_compare() {
+        let mut s = FixedSizeString::<8>::new();
+        assert!(s.is_empty());
+        s.append(b"ab");
+        s.append(b"cd");
+        assert!(s == b"abcd".as_slice());
+        assert!(s == FixedSizeString::from(b"abcd"));
+        assert!(s != FixedSizeString::from(b"abc"));
+    }
+}
@@ -0,0 +1,178 @@
+//
+// formatting of numbers and adapter to `core::fmt`
+//
+// note: `core::fmt` formatting machinery is large thus prefer `Number` on the firmware
+//
+// note: numbers printed by `i`, `mi`, `heapcheck`, `sds` and `ls` use `Number` while
+//       `p_u32` remains the idiom for plain decimal ids, quantities and line numbers as printed by
+//       `export` and by `print_error` of scripts
+// note: firmware size budget on release build with `opt-level = "z"`
+//       `Number` first call:                           +632 B
+//       `Number` each further call:                     +52 B
+//       `Number` signed, width, zero pad, hex, binary:   +8 B
+//       `Writer` with `write!` of a `&str`:             +28 B
+//       `Writer` with `write!` of an `u32`:           +2896 B
+//       `Writer` with `write!` of `{}{:08x}`:         +3268 B
+//
+use super::api::Printer;
+use core::fmt;
+
+/// Number formatted when printed.
+#[derive(Clone, Copy)]
+pub struct Number {
+    magnitude: u32,
+    negative: bool,
+    radix: u32,
+    width: u8,
+    zero_pad: bool,
+}
+
+impl Number {
+    /// Unsigned decimal.
+    pub const fn u32(num: u32) -> Self {
+        Self {
+            magnitude: num,
+            negative: false,
+            radix: 10,
+            width: 0,
+            zero_pad: false,
+        }
+    }
+
+    /// Signed decimal.
+    pub const fn i32(num: i32) -> Self {
+        Self {
+            magnitude: num.unsigned_abs(),
+            negative: num < 0,
+            ..Self::u32(0)
+        }
+    }
+
+    /// Hexadecimal with upper case digits and no prefix.
+    #[must_use]
+    pub const fn hex(self) -> Self {
+        Self { radix: 16, ..self }
+    }
+
+    /// Binary without prefix.
+    #[must_use]
+    pub const fn binary(self) -> Self {
+        Self { radix: 2, ..self }
+    }
+
+    /// Minimum printed width, padded with spaces before the number.
+    #[must_use]
+    pub const fn width(self, width: u8) -> Self {
+        Self { width, ..self }
+    }
+
+    /// Pads with zeros after the sign instead of spaces.
+    #[must_use]
+    pub const fn zero_pad(self) -> Self {
+        Self {
+            zero_pad: true,
+            ..self
+        }
+    }
+
+    /// Prints the number.
+    #[expect(clippy::cast_possible_truncation, reason = "digit is less than radix")]
+    #[inline(never)]
+    pub fn print(self, printer: &dyn Printer) {
+        // note: 32 is maximum digits of u32 in binary
+        let mut buf = [0_u8; 32];
+        let mut pos = buf.len();
+        let mut n = self.magnitude;
+        loop {
+            let digit = (n % self.radix) as u8;
+            pos -= 1;
+            buf[pos] = if digit < 10 {
+                b'0' + digit
+            } else {
+                b'A' + digit - 10
+            };
+            n /= self.radix;
+            if n == 0 {
+                break;
+            }
+        }
+
+        let len = buf.len() - pos + usize::from(self.negative);
+        let padding = usize::from(self.width).saturating_sub(len);
+        if !self.zero_pad {
+            pad(printer, b' ', padding);
+        }
+        if self.negative {
+            printer.pb(b'-');
+        }
+        if self.zero_pad {
+            pad(printer, b'0', padding);
+        }
+        printer.p(&buf[pos..]);
+    }
+}
+
+fn pad(printer: &dyn Printer, byte: u8, count: usize) {
+    for _ in 0..count {
+        printer.pb(byte);
+    }
+}
+
+/// Adapter to `core::fmt::Write` so that `write!` can print to a `Printer`.
+pub struct Writer<'a>(pub &'a dyn Printer);
+
+impl fmt::Write for Writer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.p(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api_memory::PrinterMemory;
+    use alloc::vec::Vec;
+    use core::fmt::Write;
+
+    fn printed(number: Number) -> Vec<u8> {
+        let printer = PrinterMemory::new();
+        number.print(&printer);
+        printer.take()
+    }
+
+    #[test]
+    fn prints_decimals() {
+        assert_eq!(printed(Number::u32(0)), b"0");
+        assert_eq!(printed(Number::u32(u32::MAX)), b"4294967295");
+        assert_eq!(printed(Number::i32(-42)), b"-42");
+        assert_eq!(printed(Number::i32(i32::MIN)), b"-2147483648");
+        assert_eq!(printed(Number::i32(7)), b"7");
+    }
+
+    #[test]
+    fn pads_to_width() {
+        assert_eq!(printed(Number::u32(42).width(5)), b"   42");
+        assert_eq!(printed(Number::i32(-42).width(5)), b"  -42");
+        assert_eq!(printed(Number::i32(-42).width(5).zero_pad()), b"-0042");
+        assert_eq!(printed(Number::u32(12345).width(3)), b"12345");
+    }
+
+    #[test]
+    fn prints_hex_and_binary() {
+        assert_eq!(printed(Number::u32(0xbeef).hex()), b"BEEF");
+        assert_eq!(printed(Number::u32(0xf).hex().width(4).zero_pad()), b"000F");
+        assert_eq!(printed(Number::u32(5).binary()), b"101");
+        assert_eq!(
+            printed(Number::u32(u32::MAX).binary()),
+            [b'1'; 32].as_slice()
+        );
+    }
+
+    #[test]
+    fn writes_with_core_fmt() {
+        let printer = PrinterMemory::new();
+        assert!(write!(Writer(&printer), "a:{:>4}:{:04x}", -3, 255).is_ok());
+        assert_eq!(printer.take(), b"a:  -3:00ff");
+    }
+}
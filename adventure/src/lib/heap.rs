@@ -0,0 +1,591 @@
+//
+// first fit heap of blocks in a doubly linked list in address order
+//
+// note: the header is right before the user data thus an over-aligned allocation starts at an
+//       offset in a free block and the padding before it stays a free block
+//
+#![expect(
+    clippy::cast_ptr_alignment,
+    reason = "blocks are aligned to the header"
+)]
+
+use super::api::{HeapStats, Printer};
+use core::alloc::Layout;
+use core::cmp::max;
+use core::mem;
+use core::ptr;
+
+struct BlockHeader {
+    next: *mut Self, // Pointer to the next block in the list.
+    prev: *mut Self, // Pointer to the previous block in the list.
+    size: usize,     // Total size of the block, including the header.
+    is_free: bool,   // Indicates whether the block is available for allocation.
+    #[cfg(feature = "heap-debug")]
+    magic: u32, // Canary that is `BLOCK_MAGIC` unless the header is overwritten.
+    #[cfg(feature = "heap-debug")]
+    user_size: usize, // Size requested by the user, followed by the guard bytes.
+}
+
+const HEADER_SIZE: usize = mem::size_of::<BlockHeader>();
+const HEADER_ALIGN: usize = mem::align_of::<BlockHeader>();
+const MIN_BLOCK_SIZE: usize = HEADER_SIZE * 2;
+
+#[cfg(feature = "heap-debug")]
+const BLOCK_MAGIC: u32 = 0x4845_4150;
+#[cfg(feature = "heap-debug")]
+const GUARD_BYTE: u8 = 0xa5;
+
+// note: guard bytes after user data detect writes past the end of an allocation
+#[cfg(feature = "heap-debug")]
+const GUARD_SIZE_BYTES: usize = 4;
+#[cfg(not(feature = "heap-debug"))]
+const GUARD_SIZE_BYTES: usize = 0;
+
+pub struct Heap {
+    block_head: *mut BlockHeader,
+    ram_size_bytes: usize,
+    in_use_bytes: usize, // Bytes of blocks in use including headers.
+    peak_in_use_bytes: usize,
+}
+
+impl Heap {
+    /// Heap without memory until `init`.
+    pub const fn new() -> Self {
+        Self {
+            block_head: ptr::null_mut(),
+            ram_size_bytes: 0,
+            in_use_bytes: 0,
+            peak_in_use_bytes: 0,
+        }
+    }
+
+    /// Makes the memory from `start` to `end` one free block.
+    ///
+    /// # Safety
+    ///
+    /// The memory must be writable, unused by anything else and outlive the heap.
+    pub unsafe fn init(&mut self, start: usize, end: usize) {
+        // align heap start upward to align_of::<BlockHeader>()
+        let aligned_start = start.next_multiple_of(HEADER_ALIGN);
+        let usable_size = (end - aligned_start) / HEADER_ALIGN * HEADER_ALIGN;
+
+        let first_block = aligned_start as *mut BlockHeader;
+        unsafe {
+            *first_block = BlockHeader {
+                next: ptr::null_mut(),
+                prev: ptr::null_mut(),
+                size: usable_size,
+                is_free: true,
+                #[cfg(feature = "heap-debug")]
+                magic: BLOCK_MAGIC,
+                #[cfg(feature = "heap-debug")]
+                user_size: 0,
+            };
+        }
+        self.block_head = first_block;
+        self.ram_size_bytes = usable_size;
+        self.in_use_bytes = 0;
+        self.peak_in_use_bytes = 0;
+    }
+
+    /// Allocates from the first free block that fits `layout` including its alignment.
+    /// Returns null if no block fits.
+    ///
+    /// # Safety
+    ///
+    /// The heap must be initialized.
+    pub unsafe fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        let size = Self::block_size(layout);
+
+        // find first suitable free block
+        unsafe {
+            let mut current = self.block_head;
+
+            while !current.is_null() {
+                let padding = Self::padding(current, layout.align());
+                if (*current).is_free && (*current).size >= padding + size {
+                    // found a suitable block
+                    let block = if padding == 0 {
+                        current
+                    } else {
+                        // note: padding before the aligned block stays free
+                        Self::split_block_at(current, padding);
+                        (*current).next
+                    };
+
+                    Self::split_block(block, size);
+
+                    (*block).is_free = false;
+                    self.count_in_use((*block).size, 0);
+                    #[cfg(feature = "heap-debug")]
+                    Self::set_guard(block, layout.size());
+
+                    return block.cast::<u8>().add(HEADER_SIZE);
+                }
+
+                current = (*current).next;
+            }
+        }
+
+        // no suitable block found
+        // note: fallible allocations such as `Vec::try_reserve` recover from null
+        ptr::null_mut()
+    }
+
+    /// Frees the block at `ptr` and merges it with adjacent free blocks.
+    /// Returns false and frees nothing if the block is corrupt.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `alloc` or `resize` of this heap and not freed.
+    pub unsafe fn dealloc(&mut self, ptr: *mut u8) -> bool {
+        unsafe {
+            // get the block header
+            let block = ptr.sub(HEADER_SIZE).cast::<BlockHeader>();
+
+            #[cfg(feature = "heap-debug")]
+            if !Self::is_intact(block) {
+                return false;
+            }
+
+            // mark block as free
+            (*block).is_free = true;
+            self.count_in_use(0, (*block).size);
+
+            // attempt to merge with adjacent free blocks
+            let current = block;
+
+            // merge with next block if possible
+            if !(*current).next.is_null() && (*(*current).next).is_free {
+                (*current).size += (*(*current).next).size;
+                (*current).next = (*(*current).next).next;
+                if !(*current).next.is_null() {
+                    (*(*current).next).prev = current;
+                }
+            }
+
+            // merge with previous block if possible
+            if !(*current).prev.is_null() && (*(*current).prev).is_free {
+                (*(*current).prev).size += (*current).size;
+                (*(*current).prev).next = (*current).next;
+                if !(*current).next.is_null() {
+                    (*(*current).next).prev = (*current).prev;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Resizes the block at `ptr` in place to fit `layout` by shrinking it or by growing it
+    /// into the next block if that is free. Returns false if the block cannot be resized.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `alloc` or `resize` of this heap and not freed.
+    pub unsafe fn resize(&mut self, ptr: *mut u8, layout: Layout) -> bool {
+        let size = Self::block_size(layout);
+        unsafe {
+            let block = ptr.sub(HEADER_SIZE).cast::<BlockHeader>();
+
+            let old_size = (*block).size;
+
+            if (*block).size < size {
+                // grow into next block
+                let next = (*block).next;
+                if next.is_null() || !(*next).is_free || (*block).size + (*next).size < size {
+                    return false;
+                }
+
+                (*block).size += (*next).size;
+                (*block).next = (*next).next;
+                if !(*block).next.is_null() {
+                    (*(*block).next).prev = block;
+                }
+            }
+
+            Self::split_block(block, size);
+            self.count_in_use((*block).size, old_size);
+            #[cfg(feature = "heap-debug")]
+            Self::set_guard(block, layout.size());
+        }
+
+        true
+    }
+
+    /// Returns the size of a block including header that fits `layout`.
+    const fn block_size(layout: Layout) -> usize {
+        // note: next block header must be aligned
+        (layout.size() + HEADER_SIZE + GUARD_SIZE_BYTES).next_multiple_of(HEADER_ALIGN)
+    }
+
+    /// Returns the bytes to skip in `block` for the user data to be aligned to `align`.
+    fn padding(block: *const BlockHeader, align: usize) -> usize {
+        let user_data = block as usize + HEADER_SIZE;
+        let padding = user_data.next_multiple_of(align) - user_data;
+        if padding == 0 {
+            return 0;
+        }
+        // note: padding becomes a free block thus must fit one
+        padding
+            + MIN_BLOCK_SIZE
+                .saturating_sub(padding)
+                .next_multiple_of(align)
+    }
+
+    /// Splits `block` at `size` if it is significantly larger. The remainder becomes a free
+    /// block that is merged with the next block if it is free.
+    unsafe fn split_block(block: *mut BlockHeader, size: usize) {
+        unsafe {
+            if (*block).size <= size + MIN_BLOCK_SIZE {
+                return;
+            }
+
+            Self::split_block_at(block, size);
+
+            // merge remainder with next block if possible
+            let new_block = (*block).next;
+            let next = (*new_block).next;
+            if !next.is_null() && (*next).is_free {
+                (*new_block).size += (*next).size;
+                (*new_block).next = (*next).next;
+                if !(*next).next.is_null() {
+                    (*(*next).next).prev = new_block;
+                }
+            }
+        }
+    }
+
+    /// Splits `block` at `offset` into two blocks where the second is free.
+    unsafe fn split_block_at(block: *mut BlockHeader, offset: usize) {
+        unsafe {
+            let new_block = block.cast::<u8>().add(offset).cast::<BlockHeader>();
+            *new_block = BlockHeader {
+                next: (*block).next,
+                prev: block,
+                size: (*block).size - offset,
+                is_free: true,
+                #[cfg(feature = "heap-debug")]
+                magic: BLOCK_MAGIC,
+                #[cfg(feature = "heap-debug")]
+                user_size: 0,
+            };
+
+            if !(*block).next.is_null() {
+                (*(*block).next).prev = new_block;
+            }
+
+            (*block).size = offset;
+            (*block).next = new_block;
+        }
+    }
+
+    /// Updates bytes in use and its peak with an allocated block of `added` bytes and a freed
+    /// block of `removed` bytes.
+    fn count_in_use(&mut self, added: usize, removed: usize) {
+        self.in_use_bytes = self.in_use_bytes + added - removed;
+        self.peak_in_use_bytes = max(self.peak_in_use_bytes, self.in_use_bytes);
+    }
+
+    /// Records `user_size` of allocated `block` and writes the guard bytes after it.
+    #[cfg(feature = "heap-debug")]
+    unsafe fn set_guard(block: *mut BlockHeader, user_size: usize) {
+        unsafe {
+            (*block).user_size = user_size;
+            let guard = block.cast::<u8>().add(HEADER_SIZE + user_size);
+            ptr::write_bytes(guard, GUARD_BYTE, GUARD_SIZE_BYTES);
+        }
+    }
+
+    /// Returns true if the header canary and, for an allocated block, the guard bytes are
+    /// intact.
+    #[cfg(feature = "heap-debug")]
+    unsafe fn is_intact(block: *const BlockHeader) -> bool {
+        unsafe {
+            if (*block).magic != BLOCK_MAGIC {
+                return false;
+            }
+            if (*block).is_free {
+                return true;
+            }
+            let guard = block.cast::<u8>().add(HEADER_SIZE + (*block).user_size);
+            (0..GUARD_SIZE_BYTES).all(|i| *guard.add(i) == GUARD_BYTE)
+        }
+    }
+
+    /// Walks the block list, prints violations with block addresses and returns their count.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "addresses are 32 bits on target"
+    )]
+    pub fn check(&self, printer: &dyn Printer) -> u32 {
+        let mut errors = 0;
+        let mut report = |block: *const BlockHeader, message: &[u8]| {
+            printer.p_hex_u32(block as usize as u32, true);
+            printer.p(b": ");
+            printer.pl(message);
+            errors += 1;
+        };
+
+        unsafe {
+            let head = self.block_head;
+            let heap_end = head as usize + self.ram_size_bytes;
+            let mut total = 0;
+            let mut prev: *mut BlockHeader = ptr::null_mut();
+            let mut current = head;
+            while !current.is_null() {
+                #[cfg(feature = "heap-debug")]
+                if (*current).magic != BLOCK_MAGIC {
+                    // note: rest of header is garbage
+                    report(current, b"header overwritten");
+                    break;
+                }
+
+                if (*current).prev != prev {
+                    report(current, b"prev does not link to previous block");
+                }
+
+                let size = (*current).size;
+                let end = current as usize + size;
+                if size < HEADER_SIZE || !size.is_multiple_of(HEADER_ALIGN) || end > heap_end {
+                    report(current, b"size out of heap");
+                    break;
+                }
+                total += size;
+
+                if (*current).is_free && !prev.is_null() && (*prev).is_free {
+                    report(current, b"free block not merged with previous");
+                }
+
+                #[cfg(feature = "heap-debug")]
+                if !Self::is_intact(current) {
+                    report(current, b"guard bytes overwritten");
+                }
+
+                let next = (*current).next;
+                if !next.is_null() && next as usize != end {
+                    report(current, b"next is not at end of block");
+                    break;
+                }
+
+                prev = current;
+                current = next;
+            }
+
+            if total != self.ram_size_bytes {
+                report(head, b"total size of blocks differs from ram size");
+            }
+        }
+
+        errors
+    }
+
+    /// Returns bytes in use and the free block figures from a walk of the block list.
+    /// note: allocation counters are kept by the caller
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    pub fn stats(&self) -> HeapStats {
+        let mut stats = HeapStats {
+            in_use_bytes: self.in_use_bytes as u32,
+            peak_in_use_bytes: self.peak_in_use_bytes as u32,
+            ..HeapStats::default()
+        };
+
+        unsafe {
+            let mut current = self.block_head;
+            while !current.is_null() {
+                if (*current).is_free {
+                    let size = (*current).size as u32;
+                    stats.free_bytes += size;
+                    stats.free_block_count += 1;
+                    stats.largest_free_block_bytes = max(stats.largest_free_block_bytes, size);
+                }
+                current = (*current).next;
+            }
+        }
+
+        stats
+    }
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "usize is 32 bits on target"
+    )]
+    pub fn debug_block_list(&self, printer: &dyn Printer) {
+        unsafe {
+            let mut current = self.block_head;
+            let mut total_user_allocated: usize = 0;
+            let mut total_allocated_with_headers: usize = 0;
+            while !current.is_null() {
+                printer.p_hex_u32(current as usize as u32, true);
+                printer.p(b", size: ");
+                printer.p_hex_u32((*current).size as u32, true);
+                if !(*current).is_free {
+                    total_allocated_with_headers += (*current).size;
+                    total_user_allocated += (*current).size - HEADER_SIZE;
+                }
+                printer.p(b", free: ");
+                printer.pb(if (*current).is_free { b'y' } else { b'n' });
+                printer.nl();
+
+                current = (*current).next;
+            }
+            printer.nl();
+            printer.p(b"ram size: ");
+            printer.p_u32(self.ram_size_bytes as u32);
+            printer.pl(b" bytes");
+            printer.p(b"total user allocated: ");
+            printer.p_u32(total_user_allocated as u32);
+            printer.pl(b" bytes");
+            printer.p(b"total allocated including headers: ");
+            printer.p_u32(total_allocated_with_headers as u32);
+            printer.pl(b" bytes");
+            printer.p(b"block header size: ");
+            printer.p_u32(HEADER_SIZE as u32);
+            printer.pl(b" bytes");
+            printer.p(b"min block size: ");
+            printer.p_u32(MIN_BLOCK_SIZE as u32);
+            printer.pl(b" bytes");
+        }
+    }
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api_memory::PrinterMemory;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    // note: heap starts at this alignment so that padding of blocks does not depend on the host
+    const MEMORY_ALIGN: usize = 4096;
+
+    /// Heap of `size` bytes in `memory` that must outlive it.
+    fn heap(memory: &mut Vec<u8>, size: usize) -> Heap {
+        memory.resize(size + MEMORY_ALIGN, 0);
+        let start = (memory.as_mut_ptr() as usize).next_multiple_of(MEMORY_ALIGN);
+        let mut heap = Heap::new();
+        unsafe { heap.init(start, start + size) };
+        heap
+    }
+
+    fn layout(size: usize, align: usize) -> Layout {
+        Layout::from_size_align(size, align).expect("layout should be valid")
+    }
+
+    fn assert_intact(heap: &Heap) {
+        let printer = PrinterMemory::new();
+        let errors = heap.check(&printer);
+        assert_eq!(
+            errors,
+            0,
+            "{}",
+            core::str::from_utf8(&printer.take()).unwrap_or("")
+        );
+    }
+
+    #[test]
+    fn allocates_aligned_for_power_of_two_alignments() {
+        let mut memory = vec![];
+        let mut heap = heap(&mut memory, 128 * 1024);
+
+        let mut allocations = Vec::new();
+        for align in (0..=12).map(|x| 1 << x) {
+            for size in [1, 3, 24, 100, 513] {
+                let ptr = unsafe { heap.alloc(layout(size, align)) };
+                assert!(!ptr.is_null());
+                assert!((ptr as usize).is_multiple_of(align), "align {align}");
+                unsafe { ptr::write_bytes(ptr, 0xee, size) };
+                allocations.push(ptr);
+            }
+        }
+        assert_intact(&heap);
+
+        // free every other first so that freed blocks are merged from both sides
+        for ptr in allocations
+            .iter()
+            .step_by(2)
+            .chain(allocations.iter().skip(1).step_by(2))
+        {
+            assert!(unsafe { heap.dealloc(*ptr) });
+            assert_intact(&heap);
+        }
+
+        let stats = heap.stats();
+        assert_eq!(stats.in_use_bytes, 0);
+        assert_eq!(stats.free_block_count, 1);
+        assert_eq!(stats.free_bytes as usize, heap.ram_size_bytes);
+    }
+
+    #[test]
+    fn reuses_padding_before_aligned_block() {
+        let mut memory = vec![];
+        let mut heap = heap(&mut memory, 8 * 1024);
+
+        let aligned = unsafe { heap.alloc(layout(8, 1024)) };
+        assert!((aligned as usize).is_multiple_of(1024));
+        let stats = heap.stats();
+        assert_eq!(stats.free_block_count, 2);
+
+        let small = unsafe { heap.alloc(layout(8, 4)) };
+        assert!((small as usize) < aligned as usize);
+        assert_intact(&heap);
+    }
+
+    #[test]
+    fn resizes_aligned_block_in_place() {
+        let mut memory = vec![];
+        let mut heap = heap(&mut memory, 8 * 1024);
+
+        let ptr = unsafe { heap.alloc(layout(10, 64)) };
+        assert!(unsafe { heap.resize(ptr, layout(1000, 64)) });
+        assert!(unsafe { heap.resize(ptr, layout(20, 64)) });
+        assert_intact(&heap);
+        assert!(heap.stats().in_use_bytes < 100);
+
+        // note: small allocations would fit in the padding before `ptr`
+        let next = unsafe { heap.alloc(layout(4000, 4)) };
+        assert!(!next.is_null());
+        assert!(!unsafe { heap.resize(ptr, layout(1000, 64)) });
+        assert_intact(&heap);
+    }
+
+    #[test]
+    fn returns_null_when_full() {
+        let mut memory = vec![];
+        let mut heap = heap(&mut memory, 1024);
+
+        assert!(unsafe { heap.alloc(layout(2048, 4)) }.is_null());
+        assert!(unsafe { heap.alloc(layout(256, 2048)) }.is_null());
+        assert!(!unsafe { heap.alloc(layout(256, 4)) }.is_null());
+        assert_intact(&heap);
+    }
+
+    #[test]
+    fn check_reports_broken_links() {
+        let mut memory = vec![];
+        let mut heap = heap(&mut memory, 8 * 1024);
+
+        let first = unsafe { heap.alloc(layout(16, 4)) };
+        let second = unsafe { heap.alloc(layout(16, 4)) };
+        assert!(!second.is_null());
+        unsafe {
+            let block = second.sub(HEADER_SIZE).cast::<BlockHeader>();
+            (*block).prev = ptr::null_mut();
+        }
+
+        let printer = PrinterMemory::new();
+        assert_eq!(heap.check(&printer), 1);
+        let output = printer.take();
+        assert!(output.ends_with(b": prev does not link to previous block\n"));
+        assert!(!first.is_null());
+    }
+}
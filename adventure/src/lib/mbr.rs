@@ -0,0 +1,100 @@
+//
+// master boot record partition table
+//
+use super::api::{Hardware, SDCARD_SECTOR_SIZE_BYTES};
+
+pub const PARTITION_COUNT: usize = 4;
+
+const PARTITION_TABLE_OFFSET: usize = 446;
+const PARTITION_ENTRY_SIZE: usize = 16;
+const SIGNATURE_OFFSET: usize = 510;
+
+pub const KIND_EMPTY: u8 = 0x00;
+pub const KIND_FAT16_SMALL: u8 = 0x04;
+pub const KIND_FAT16: u8 = 0x06;
+pub const KIND_FAT32_CHS: u8 = 0x0b;
+pub const KIND_FAT32_LBA: u8 = 0x0c;
+pub const KIND_FAT16_LBA: u8 = 0x0e;
+
+#[derive(Clone, Copy, Default)]
+pub struct Partition {
+    pub kind: u8,
+    pub first_sector: u32,
+    pub sector_count: u32,
+}
+
+impl Partition {
+    pub const fn is_fat(&self) -> bool {
+        matches!(
+            self.kind,
+            KIND_FAT16_SMALL | KIND_FAT16 | KIND_FAT32_CHS | KIND_FAT32_LBA | KIND_FAT16_LBA
+        )
+    }
+}
+
+/// Returns true if `sector` ends with the boot signature 0x55 0xaa.
+pub fn has_signature(sector: &[u8]) -> bool {
+    sector[SIGNATURE_OFFSET..SIGNATURE_OFFSET + 2] == [0x55, 0xaa]
+}
+
+/// Parses the partition table of the master boot record in `sector`.
+/// Returns `None` if the sector has no boot signature.
+pub fn parse(sector: &[u8]) -> Option<[Partition; PARTITION_COUNT]> {
+    if !has_signature(sector) {
+        return None;
+    }
+
+    let mut partitions = [Partition::default(); PARTITION_COUNT];
+    for (i, partition) in partitions.iter_mut().enumerate() {
+        let entry = &sector[PARTITION_TABLE_OFFSET + i * PARTITION_ENTRY_SIZE..];
+        *partition = Partition {
+            kind: entry[4],
+            first_sector: u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]),
+            sector_count: u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]),
+        };
+    }
+
+    Some(partitions)
+}
+
+/// Reads the master boot record from sector 0 and returns the partition table.
+pub fn read(hardware: &dyn Hardware) -> Option<[Partition; PARTITION_COUNT]> {
+    let mut sector = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    hardware.sdcard_read_blocking(0, &mut sector);
+    parse(&sector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sector_with_partition(index: usize, kind: u8, first: u32, count: u32) -> [u8; 512] {
+        let mut sector = [0_u8; 512];
+        let entry = &mut sector[PARTITION_TABLE_OFFSET + index * PARTITION_ENTRY_SIZE..];
+        entry[4] = kind;
+        entry[8..12].copy_from_slice(&first.to_le_bytes());
+        entry[12..16].copy_from_slice(&count.to_le_bytes());
+        sector[510] = 0x55;
+        sector[511] = 0xaa;
+        sector
+    }
+
+    #[test]
+    fn parses_partition_entries() {
+        let sector = sector_with_partition(1, KIND_FAT32_LBA, 2048, 0x0001_0000);
+        let partitions = parse(&sector).expect("signature should be valid");
+        assert_eq!(partitions[0].kind, KIND_EMPTY);
+        assert!(!partitions[0].is_fat());
+        assert_eq!(partitions[1].kind, KIND_FAT32_LBA);
+        assert_eq!(partitions[1].first_sector, 2048);
+        assert_eq!(partitions[1].sector_count, 0x0001_0000);
+        assert!(partitions[1].is_fat());
+    }
+
+    #[test]
+    fn rejects_missing_signature() {
+        let mut sector = sector_with_partition(0, KIND_FAT16, 1, 2);
+        sector[511] = 0;
+        assert!(parse(&sector).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+//
+// reviewed: 2025-04-21
+//           2026-08-21
+//
+use crate::lib::arena::{Arena, Id};
+use crate::lib::fixed_size_string::FixedSizeString;
+use alloc::vec::Vec;
+
+pub type LocationId = Id<Location>;
+pub type LinkNameId = Id<LinkName>;
+pub type ObjectId = Id<Object>;
+pub type EntityId = Id<Entity>;
+pub type Name = FixedSizeString<32>;
+pub type Note = FixedSizeString<64>;
+pub type Message = FixedSizeString<128>;
+
+/// Weight of a new object.
+pub const DEFAULT_WEIGHT: u32 = 1;
+/// Bulk of a new object.
+pub const DEFAULT_BULK: u32 = 1;
+/// Bulk that fits in a new container.
+pub const DEFAULT_CAPACITY: u32 = 10;
+/// Weight that a new entity can carry.
+pub const DEFAULT_MAX_WEIGHT: u32 = 50;
+/// Bulk that a new entity can carry.
+pub const DEFAULT_MAX_BULK: u32 = 10;
+
+pub struct World {
+    pub objects: Arena<Object>,
+    pub entities: Arena<Entity>,
+    pub locations: Arena<Location>,
+    pub link_names: Arena<LinkName>,
+}
+
+pub struct Location {
+    pub name: Name,
+    pub note: Note,
+    pub links: Vec<Link>,
+    pub objects: Vec<ObjectId>,
+    pub entities: Vec<EntityId>,
+}
+
+/// Exit to a location. A link back from the location is not required.
+pub struct Link {
+    pub link_name: LinkNameId,
+    pub location: LocationId,
+    pub door: Option<Door>,
+}
+
+/// Door of an exit.
+/// note: a door and the door of the link back, if any, are kept in the same state
+#[derive(Clone, Copy)]
+pub struct Door {
+    pub state: DoorState,
+    /// Object that locks and unlocks the door or `None` if it cannot be locked.
+    pub key: Option<ObjectId>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    Open,
+    Closed,
+    Locked,
+}
+
+pub struct LinkName {
+    pub name: Name,
+}
+
+pub struct Object {
+    pub name: Name,
+    /// Shown by examine and look when not empty.
+    pub description: Note,
+    /// Weight of the object alone, objects in it add their weight.
+    pub weight: u32,
+    /// Room taken in hands or in a container.
+    pub bulk: u32,
+    /// True if objects can be put in it.
+    pub container: bool,
+    /// Bulk of the objects that fit in the container.
+    pub capacity: u32,
+    /// Objects in the container.
+    pub objects: Vec<ObjectId>,
+}
+
+pub struct Entity {
+    pub name: Name,
+    /// Shown by examine and look when not empty.
+    pub description: Note,
+    pub location: LocationId,
+    pub objects: Vec<ObjectId>,
+    pub messages: Vec<Message>,
+    /// Weight of the inventory that can be carried.
+    pub max_weight: u32,
+    /// Bulk of the inventory that can be carried.
+    pub max_bulk: u32,
+}
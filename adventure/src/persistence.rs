@@ -0,0 +1,887 @@
+//
+// saves and restores the world on the SD card
+//
+// layout: header sector followed by the payload in consecutive sectors
+//
+//   header: magic (4 bytes), version (u32), payload length (u32), payload checksum (u32)
+//  payload: slot counts of objects, entities, locations and link names followed by each collection
+//     slot: generation (u32), occupied (u8) followed by the element if occupied
+//     door: state (u8) being 0 for no door, 1 open, 2 closed, 3 locked followed by the key as
+//           present (u8) and id if there is a door
+//
+// note: integers are little endian u32, strings are a u8 length followed by the bytes, ids are
+//       slot index and generation
+//
+use crate::lib::api::{Hardware, SDCARD_SECTOR_SIZE_BYTES, SDCARD_STATUS_READY};
+use crate::lib::arena::{Arena, Id};
+use crate::lib::fixed_size_string::FixedSizeString;
+use crate::lib::mbr;
+use crate::model::{Door, DoorState, Entity, Link, LinkName, Location, Object, ObjectId, World};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// First sector of the saved world.
+/// note: cards formatted on a PC usually start the first partition at sector 2048, older cards
+///       at 63 or 135 thus save and load refuse if a partition overlaps the sectors used
+const FIRST_SECTOR: u32 = 1024;
+
+/// Maximum number of sectors used including the header.
+const MAX_SECTORS: usize = 1024;
+
+const MAGIC: &[u8; 4] = b"WRLD";
+const VERSION: u32 = 6;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub enum Error {
+    CardNotReady,
+    PartitionOverlap,
+    TooLarge,
+    OutOfMemory,
+    NoImage,
+    UnsupportedVersion,
+    Corrupt,
+}
+
+pub fn save(world: &World, hardware: &mut dyn Hardware) -> Result<()> {
+    if hardware.sdcard_status() != SDCARD_STATUS_READY {
+        return Err(Error::CardNotReady);
+    }
+    if overlaps_partition(hardware) {
+        return Err(Error::PartitionOverlap);
+    }
+
+    let len = serialized_len(world);
+    if len.div_ceil(SDCARD_SECTOR_SIZE_BYTES) + 1 > MAX_SECTORS {
+        return Err(Error::TooLarge);
+    }
+
+    // note: reserve before serializing so that running out of memory is reported
+    let mut payload = Vec::new();
+    if payload.try_reserve_exact(len).is_err() {
+        return Err(Error::OutOfMemory);
+    }
+    serialize(world, &mut payload);
+
+    let chunks = payload.chunks(SDCARD_SECTOR_SIZE_BYTES);
+    for (sector, chunk) in (FIRST_SECTOR + 1..).zip(chunks) {
+        let mut buf = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        hardware.sdcard_write_blocking(sector, &buf);
+    }
+
+    // write header last so that an interrupted save fails the checksum at load
+    let mut header = Vec::new();
+    if header.try_reserve_exact(MAGIC.len() + 3 * 4).is_err() {
+        return Err(Error::OutOfMemory);
+    }
+    header.extend_from_slice(MAGIC);
+    put_u32(&mut header, VERSION);
+    put_usize(&mut header, payload.len());
+    put_u32(&mut header, crc32(&payload));
+
+    let mut buf = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    buf[..header.len()].copy_from_slice(&header);
+    hardware.sdcard_write_blocking(FIRST_SECTOR, &buf);
+
+    Ok(())
+}
+
+pub fn load(hardware: &dyn Hardware) -> Result<World> {
+    if hardware.sdcard_status() != SDCARD_STATUS_READY {
+        return Err(Error::CardNotReady);
+    }
+    if overlaps_partition(hardware) {
+        return Err(Error::PartitionOverlap);
+    }
+
+    let mut buf = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+    hardware.sdcard_read_blocking(FIRST_SECTOR, &mut buf);
+
+    let mut header = Reader { bytes: &buf };
+    if header.bytes(MAGIC.len()) != Some(MAGIC) {
+        return Err(Error::NoImage);
+    }
+    if header.u32() != Some(VERSION) {
+        return Err(Error::UnsupportedVersion);
+    }
+    let (Some(len), Some(checksum)) = (header.u32(), header.u32()) else {
+        return Err(Error::Corrupt);
+    };
+    let len = len as usize;
+    if len.div_ceil(SDCARD_SECTOR_SIZE_BYTES) + 1 > MAX_SECTORS {
+        return Err(Error::Corrupt);
+    }
+
+    let mut payload = Vec::new();
+    if payload.try_reserve_exact(len).is_err() {
+        return Err(Error::OutOfMemory);
+    }
+    let mut sector = FIRST_SECTOR + 1;
+    while payload.len() < len {
+        hardware.sdcard_read_blocking(sector, &mut buf);
+        let n = (len - payload.len()).min(buf.len());
+        payload.extend_from_slice(&buf[..n]);
+        sector += 1;
+    }
+
+    if crc32(&payload) != checksum {
+        return Err(Error::Corrupt);
+    }
+
+    deserialize(&mut Reader { bytes: &payload }).ok_or(Error::Corrupt)
+}
+
+/// Returns true if a partition in the master boot record overlaps the sectors of the saved world.
+/// note: a card without a master boot record has no partitions
+#[expect(clippy::cast_possible_truncation, reason = "`MAX_SECTORS` fits in u32")]
+fn overlaps_partition(hardware: &dyn Hardware) -> bool {
+    let Some(partitions) = mbr::read(hardware) else {
+        return false;
+    };
+    let end = FIRST_SECTOR + MAX_SECTORS as u32;
+    partitions.iter().any(|x| {
+        x.kind != mbr::KIND_EMPTY
+            && x.sector_count != 0
+            && x.first_sector < end
+            && x.first_sector.saturating_add(x.sector_count) > FIRST_SECTOR
+    })
+}
+
+fn serialize(world: &World, out: &mut Vec<u8>) {
+    put_usize(out, world.objects.slot_count());
+    put_usize(out, world.entities.slot_count());
+    put_usize(out, world.locations.slot_count());
+    put_usize(out, world.link_names.slot_count());
+
+    for (generation, object) in world.objects.slots() {
+        if let Some(object) = put_slot(out, generation, object) {
+            put_bytes(out, &object.name);
+            put_bytes(out, &object.description);
+            put_u32(out, object.weight);
+            put_u32(out, object.bulk);
+            out.push(u8::from(object.container));
+            put_u32(out, object.capacity);
+            put_ids(out, &object.objects);
+        }
+    }
+
+    for (generation, entity) in world.entities.slots() {
+        if let Some(entity) = put_slot(out, generation, entity) {
+            put_bytes(out, &entity.name);
+            put_bytes(out, &entity.description);
+            put_id(out, entity.location);
+            put_ids(out, &entity.objects);
+            put_usize(out, entity.messages.len());
+            for message in &entity.messages {
+                put_bytes(out, message);
+            }
+            put_u32(out, entity.max_weight);
+            put_u32(out, entity.max_bulk);
+        }
+    }
+
+    for (generation, location) in world.locations.slots() {
+        if let Some(location) = put_slot(out, generation, location) {
+            put_bytes(out, &location.name);
+            put_bytes(out, &location.note);
+            put_usize(out, location.links.len());
+            for link in &location.links {
+                put_id(out, link.link_name);
+                put_id(out, link.location);
+                put_door(out, link.door);
+            }
+            put_ids(out, &location.objects);
+            put_ids(out, &location.entities);
+        }
+    }
+
+    for (generation, link_name) in world.link_names.slots() {
+        if let Some(link_name) = put_slot(out, generation, link_name) {
+            put_bytes(out, &link_name.name);
+        }
+    }
+}
+
+/// Returns the length of the payload put by `serialize`.
+fn serialized_len(world: &World) -> usize {
+    const ID: usize = 8;
+    let bytes = |x: &[u8]| 1 + x.len();
+    let ids = |count: usize| 4 + count * ID;
+    let door = |x: Option<Door>| x.map_or(1, |x| 2 + x.key.map_or(0, |_| ID));
+
+    let slots = world.objects.slot_count()
+        + world.entities.slot_count()
+        + world.locations.slot_count()
+        + world.link_names.slot_count();
+
+    let objects: usize = world
+        .objects
+        .values()
+        .map(|x| bytes(&x.name) + bytes(&x.description) + 13 + ids(x.objects.len()))
+        .sum();
+
+    let entities: usize = world
+        .entities
+        .values()
+        .map(|x| {
+            bytes(&x.name)
+                + bytes(&x.description)
+                + ID
+                + ids(x.objects.len())
+                + 4
+                + x.messages.iter().map(|x| bytes(x)).sum::<usize>()
+                + 8
+        })
+        .sum();
+
+    let locations: usize = world
+        .locations
+        .values()
+        .map(|x| {
+            bytes(&x.name)
+                + bytes(&x.note)
+                + 4
+                + x.links.iter().map(|x| 2 * ID + door(x.door)).sum::<usize>()
+                + ids(x.objects.len())
+                + ids(x.entities.len())
+        })
+        .sum();
+
+    let link_names: usize = world.link_names.values().map(|x| bytes(&x.name)).sum();
+
+    16 + 5 * slots + objects + entities + locations + link_names
+}
+
+fn deserialize(reader: &mut Reader) -> Option<World> {
+    let objects_count = reader.count()?;
+    let entities_count = reader.count()?;
+    let locations_count = reader.count()?;
+    let link_names_count = reader.count()?;
+
+    let mut objects = Vec::with_capacity(objects_count);
+    for _ in 0..objects_count {
+        objects.push(reader.slot(|reader| {
+            Some(Object {
+                name: reader.string()?,
+                description: reader.string()?,
+                weight: reader.u32()?,
+                bulk: reader.u32()?,
+                container: reader.bool()?,
+                capacity: reader.u32()?,
+                objects: reader.ids()?,
+            })
+        })?);
+    }
+
+    let mut entities = Vec::with_capacity(entities_count);
+    for _ in 0..entities_count {
+        entities.push(reader.slot(|reader| {
+            let name = reader.string()?;
+            let description = reader.string()?;
+            let location = reader.id()?;
+            let objects = reader.ids()?;
+            let messages_count = reader.count()?;
+            let mut messages = Vec::with_capacity(messages_count);
+            for _ in 0..messages_count {
+                messages.push(reader.string()?);
+            }
+            let max_weight = reader.u32()?;
+            let max_bulk = reader.u32()?;
+            Some(Entity {
+                name,
+                description,
+                location,
+                objects,
+                messages,
+                max_weight,
+                max_bulk,
+            })
+        })?);
+    }
+
+    let mut locations = Vec::with_capacity(locations_count);
+    for _ in 0..locations_count {
+        locations.push(reader.slot(|reader| {
+            let name = reader.string()?;
+            let note = reader.string()?;
+            let links_count = reader.count()?;
+            let mut links = Vec::with_capacity(links_count);
+            for _ in 0..links_count {
+                links.push(Link {
+                    link_name: reader.id()?,
+                    location: reader.id()?,
+                    door: reader.door()?,
+                });
+            }
+            let objects = reader.ids()?;
+            let entities = reader.ids()?;
+            Some(Location {
+                name,
+                note,
+                links,
+                objects,
+                entities,
+            })
+        })?);
+    }
+
+    let mut link_names = Vec::with_capacity(link_names_count);
+    for _ in 0..link_names_count {
+        link_names.push(reader.slot(|reader| {
+            Some(LinkName {
+                name: reader.string()?,
+            })
+        })?);
+    }
+
+    if !reader.bytes.is_empty() {
+        return None;
+    }
+
+    let world = World {
+        objects: Arena::from_slots(objects),
+        entities: Arena::from_slots(entities),
+        locations: Arena::from_slots(locations),
+        link_names: Arena::from_slots(link_names),
+    };
+
+    (ids_resolve(&world) && objects_held_once(&world) && entities_listed_once(&world))
+        .then_some(world)
+}
+
+/// Returns true if every id in `world` refers to an element.
+/// note: guards against a corrupt image with ids out of range or of removed elements
+fn ids_resolve(world: &World) -> bool {
+    let all = |ids: &[Id<_>], arena: &Arena<_>| ids.iter().all(|&id| arena.contains(id));
+
+    world
+        .entities
+        .values()
+        .all(|x| world.locations.contains(x.location) && all(&x.objects, &world.objects))
+        && world.locations.values().all(|x| {
+            x.links.iter().all(|link| {
+                world.link_names.contains(link.link_name)
+                    && world.locations.contains(link.location)
+                    && link
+                        .door
+                        .and_then(|x| x.key)
+                        .is_none_or(|x| world.objects.contains(x))
+            }) && all(&x.objects, &world.objects)
+                && x.entities.iter().all(|&id| world.entities.contains(id))
+        })
+        && world
+            .objects
+            .values()
+            .all(|x| all(&x.objects, &world.objects))
+}
+
+/// Returns true if every object is in exactly one location, inventory or container.
+/// note: guards against a corrupt image with containers inside themselves that would make
+///       walking the contents endless
+fn objects_held_once(world: &World) -> bool {
+    let mut held = vec![false; world.objects.slot_count()];
+    let mut stack: Vec<ObjectId> = world
+        .locations
+        .values()
+        .flat_map(|x| &x.objects)
+        .chain(world.entities.values().flat_map(|x| &x.objects))
+        .copied()
+        .collect();
+
+    while let Some(id) = stack.pop() {
+        let index = id.index() as usize;
+        if held[index] {
+            return false;
+        }
+        held[index] = true;
+        if let Some(x) = world.objects.get(id) {
+            stack.extend_from_slice(&x.objects);
+        }
+    }
+
+    world
+        .objects
+        .iter()
+        .all(|(id, _)| held[id.index() as usize])
+}
+
+/// Returns true if every entity is listed by exactly one location being the one it is in.
+/// note: guards against a corrupt image with entities that `go` and `rml` would not find
+fn entities_listed_once(world: &World) -> bool {
+    let mut listed = vec![false; world.entities.slot_count()];
+
+    for (location_id, location) in world.locations.iter() {
+        for &id in &location.entities {
+            let index = id.index() as usize;
+            if listed[index]
+                || world
+                    .entities
+                    .get(id)
+                    .is_none_or(|x| x.location != location_id)
+            {
+                return false;
+            }
+            listed[index] = true;
+        }
+    }
+
+    world
+        .entities
+        .iter()
+        .all(|(id, _)| listed[id.index() as usize])
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "usize is 32 bits on target"
+)]
+fn put_usize(out: &mut Vec<u8>, value: usize) {
+    put_u32(out, value as u32);
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "strings are shorter than 256 bytes"
+)]
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.push(bytes.len() as u8);
+    out.extend_from_slice(bytes);
+}
+
+fn put_id<T>(out: &mut Vec<u8>, id: Id<T>) {
+    put_u32(out, id.index());
+    put_u32(out, id.generation());
+}
+
+fn put_ids<T>(out: &mut Vec<u8>, ids: &[Id<T>]) {
+    put_usize(out, ids.len());
+    for &id in ids {
+        put_id(out, id);
+    }
+}
+
+fn put_door(out: &mut Vec<u8>, door: Option<Door>) {
+    let Some(door) = door else {
+        out.push(0);
+        return;
+    };
+    out.push(match door.state {
+        DoorState::Open => 1,
+        DoorState::Closed => 2,
+        DoorState::Locked => 3,
+    });
+    out.push(u8::from(door.key.is_some()));
+    if let Some(key) = door.key {
+        put_id(out, key);
+    }
+}
+
+/// Puts the generation of a slot and whether it is occupied. Returns the element if it is.
+fn put_slot<'a, T>(out: &mut Vec<u8>, generation: u32, element: Option<&'a T>) -> Option<&'a T> {
+    put_u32(out, generation);
+    out.push(u8::from(element.is_some()));
+    element
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    const fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.bytes.len() {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(count);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a collection count that cannot exceed the remaining bytes.
+    /// note: guards against huge allocations from a corrupt image
+    fn count(&mut self) -> Option<usize> {
+        let count = self.u32()? as usize;
+        (count <= self.bytes.len()).then_some(count)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.bytes(1)?.first()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn id<T>(&mut self) -> Option<Id<T>> {
+        Some(Id::new(self.u32()?, self.u32()?))
+    }
+
+    fn ids<T>(&mut self) -> Option<Vec<Id<T>>> {
+        let len = self.count()?;
+        let mut ids = Vec::with_capacity(len);
+        for _ in 0..len {
+            ids.push(self.id()?);
+        }
+        Some(ids)
+    }
+
+    /// Reads a slot with the element read by `read` if the slot is occupied.
+    fn slot<T, F>(&mut self, read: F) -> Option<(u32, Option<T>)>
+    where
+        F: FnOnce(&mut Self) -> Option<T>,
+    {
+        let generation = self.u32()?;
+        match self.bytes(1)?.first()? {
+            0 => Some((generation, None)),
+            1 => Some((generation, Some(read(self)?))),
+            _ => None,
+        }
+    }
+
+    /// Reads a door as put by `put_door`. Returns `Some(None)` if there is no door.
+    #[expect(
+        clippy::option_option,
+        reason = "outer is the read failing and inner is whether there is a door"
+    )]
+    fn door(&mut self) -> Option<Option<Door>> {
+        let state = match self.bytes(1)?.first()? {
+            0 => return Some(None),
+            1 => DoorState::Open,
+            2 => DoorState::Closed,
+            3 => DoorState::Locked,
+            _ => return None,
+        };
+        let key = match self.bytes(1)?.first()? {
+            0 => None,
+            1 => Some(self.id()?),
+            _ => return None,
+        };
+        Some(Some(Door { state, key }))
+    }
+
+    fn string<const SIZE: usize>(&mut self) -> Option<FixedSizeString<SIZE>> {
+        let len = usize::from(*self.bytes(1)?.first()?);
+        if len > SIZE {
+            return None;
+        }
+        Some(FixedSizeString::from(self.bytes(len)?))
+    }
+}
+
+/// CRC-32 (IEEE 802.3) computed bitwise to keep the firmware small.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xEDB8_8320
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api_memory::HardwareMemory;
+    use crate::lib::fat_image::FatImage;
+    use crate::model::{
+        DEFAULT_BULK, DEFAULT_CAPACITY, DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, DEFAULT_WEIGHT,
+        Message, Name, Note,
+    };
+    use alloc::vec;
+
+    fn world() -> World {
+        World {
+            objects: Arena::from(vec![Object {
+                name: Name::from(b"lamp"),
+                description: Note::from(b"brass"),
+                weight: DEFAULT_WEIGHT,
+                bulk: DEFAULT_BULK,
+                container: false,
+                capacity: DEFAULT_CAPACITY,
+                objects: vec![],
+            }]),
+            entities: Arena::from(vec![Entity {
+                name: Name::from(b"u"),
+                description: Note::default(),
+                location: Id::new(1, 0),
+                objects: vec![Id::new(0, 0)],
+                messages: vec![Message::from(b"hello")],
+                max_weight: DEFAULT_MAX_WEIGHT,
+                max_bulk: DEFAULT_MAX_BULK,
+            }]),
+            locations: Arena::from(vec![
+                Location {
+                    name: Name::from(b"roome"),
+                    note: Note::from(b"dusty"),
+                    links: vec![Link {
+                        link_name: Id::new(0, 0),
+                        location: Id::new(1, 0),
+                        door: Some(Door {
+                            state: DoorState::Locked,
+                            key: Some(Id::new(0, 0)),
+                        }),
+                    }],
+                    objects: vec![],
+                    entities: vec![],
+                },
+                Location {
+                    name: Name::from(b"hall"),
+                    note: Note::default(),
+                    links: vec![Link {
+                        link_name: Id::new(1, 0),
+                        location: Id::new(0, 0),
+                        door: None,
+                    }],
+                    objects: vec![],
+                    entities: vec![Id::new(0, 0)],
+                },
+            ]),
+            link_names: Arena::from(vec![
+                LinkName {
+                    name: Name::from(b"north"),
+                },
+                LinkName {
+                    name: Name::from(b"south"),
+                },
+            ]),
+        }
+    }
+
+    fn entity(world: &mut World) -> &mut Entity {
+        world
+            .entities
+            .get_mut(Id::new(0, 0))
+            .expect("entity should exist")
+    }
+
+    fn serialized(world: &World) -> Vec<u8> {
+        let mut out = Vec::new();
+        serialize(world, &mut out);
+        out
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut hardware = HardwareMemory::new(2048);
+        let world = world();
+        assert!(save(&world, &mut hardware).is_ok());
+        assert_eq!(&hardware.sector(FIRST_SECTOR)[..4], MAGIC);
+
+        let Ok(loaded) = load(&hardware) else {
+            panic!("world should load");
+        };
+        assert_eq!(serialized(&loaded), serialized(&world));
+        let entity = loaded.entities.get(Id::new(0, 0));
+        assert_eq!(entity.map(|x| &*x.messages[0]), Some(b"hello".as_slice()));
+        let location = loaded.locations.get(Id::new(0, 0));
+        assert_eq!(location.map(|x| &*x.note), Some(b"dusty".as_slice()));
+        let door = location.and_then(|x| x.links[0].door);
+        assert!(door.is_some_and(|x| x.state == DoorState::Locked && x.key == Some(Id::new(0, 0))));
+        let object = loaded.objects.get(Id::new(0, 0));
+        assert_eq!(object.map(|x| &*x.description), Some(b"brass".as_slice()));
+    }
+
+    #[test]
+    fn load_without_image() {
+        let hardware = HardwareMemory::new(2048);
+        assert!(matches!(load(&hardware), Err(Error::NoImage)));
+    }
+
+    #[test]
+    fn load_rejects_other_version() {
+        let mut hardware = HardwareMemory::new(2048);
+        assert!(save(&world(), &mut hardware).is_ok());
+        hardware.sector_mut(FIRST_SECTOR)[4] = 1;
+        assert!(matches!(load(&hardware), Err(Error::UnsupportedVersion)));
+    }
+
+    #[test]
+    fn load_rejects_corrupt_payload() {
+        let mut hardware = HardwareMemory::new(2048);
+        assert!(save(&world(), &mut hardware).is_ok());
+        hardware.sector_mut(FIRST_SECTOR + 1)[20] ^= 1;
+        assert!(matches!(load(&hardware), Err(Error::Corrupt)));
+    }
+
+    #[test]
+    fn deserialize_rejects_out_of_range_ids() {
+        let mut world = world();
+        entity(&mut world).location = Id::new(2, 0);
+        let bytes = serialized(&world);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+    }
+
+    #[test]
+    fn load_keeps_ids_of_removed_elements_stale() {
+        let mut hardware = HardwareMemory::new(2048);
+        let mut world = world();
+        let lamp = Id::new(0, 0);
+        entity(&mut world).objects.clear();
+        assert!(world.objects.remove(lamp).is_some());
+        // note: as rmo does with the key of a door
+        if let Some(x) = world.locations.get_mut(Id::new(0, 0)) {
+            x.links[0].door = None;
+        }
+        assert!(save(&world, &mut hardware).is_ok());
+
+        let Ok(mut loaded) = load(&hardware) else {
+            panic!("world should load");
+        };
+        let pen = loaded.objects.insert(Object {
+            name: Name::from(b"pen"),
+            description: Note::default(),
+            weight: DEFAULT_WEIGHT,
+            bulk: DEFAULT_BULK,
+            container: false,
+            capacity: DEFAULT_CAPACITY,
+            objects: vec![],
+        });
+        assert_eq!(pen.index(), lamp.index());
+        assert!(!loaded.objects.contains(lamp));
+    }
+
+    #[test]
+    fn deserialize_rejects_stale_ids() {
+        let mut world = world();
+        entity(&mut world).objects = vec![Id::new(0, 1)];
+        let bytes = serialized(&world);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+    }
+
+    #[test]
+    fn deserialize_rejects_stale_door_key() {
+        let mut bad = world();
+        if let Some(x) = bad.locations.get_mut(Id::new(0, 0)) {
+            x.links[0].door = Some(Door {
+                state: DoorState::Locked,
+                key: Some(Id::new(0, 1)),
+            });
+        }
+        let bytes = serialized(&bad);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+    }
+
+    #[test]
+    fn deserialize_rejects_objects_not_held_once() {
+        let lamp = Id::new(0, 0);
+
+        let mut bad = world();
+        entity(&mut bad).objects = vec![lamp, lamp];
+        let bytes = serialized(&bad);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+
+        // note: lamp inside itself
+        let mut bad = world();
+        if let Some(x) = bad.objects.get_mut(lamp) {
+            x.objects = vec![lamp];
+        }
+        let bytes = serialized(&bad);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+
+        // note: bag inside itself is held by nothing else
+        let mut bad = world();
+        let bag = bad.objects.insert(Object {
+            name: Name::from(b"bag"),
+            description: Note::default(),
+            weight: DEFAULT_WEIGHT,
+            bulk: DEFAULT_BULK,
+            container: true,
+            capacity: DEFAULT_CAPACITY,
+            objects: vec![],
+        });
+        if let Some(x) = bad.objects.get_mut(bag) {
+            x.objects = vec![bag];
+        }
+        let bytes = serialized(&bad);
+        assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+    }
+
+    #[test]
+    fn serialized_len_is_length_of_payload() {
+        let mut world = world();
+        assert_eq!(serialized_len(&world), serialized(&world).len());
+
+        world.objects.remove(Id::new(0, 0));
+        entity(&mut world).objects.clear();
+        if let Some(x) = world.locations.get_mut(Id::new(1, 0)) {
+            x.links[0].door = Some(Door {
+                state: DoorState::Open,
+                key: None,
+            });
+        }
+        assert_eq!(serialized_len(&world), serialized(&world).len());
+    }
+
+    #[test]
+    fn deserialize_rejects_entities_not_listed_once() {
+        let u = Id::new(0, 0);
+
+        for lists in [[vec![], vec![u, u]], [vec![u], vec![]], [vec![u], vec![u]]] {
+            let mut bad = world();
+            for (x, entities) in bad.locations.values_mut().zip(lists) {
+                x.entities = entities;
+            }
+            let bytes = serialized(&bad);
+            assert!(deserialize(&mut Reader { bytes: &bytes }).is_none());
+        }
+    }
+
+    #[test]
+    fn save_rejects_too_large_world() {
+        let mut hardware = HardwareMemory::new(2048);
+        let mut world = world();
+        entity(&mut world).messages = vec![Message::from(&[b'x'; 128]); 4096];
+        assert!(matches!(save(&world, &mut hardware), Err(Error::TooLarge)));
+    }
+
+    #[test]
+    fn refuses_sectors_of_a_partition() {
+        let mut image = FatImage::fat16_at(63);
+        let before = image.hardware.sector(FIRST_SECTOR).to_vec();
+        assert!(matches!(
+            save(&world(), &mut image.hardware),
+            Err(Error::PartitionOverlap)
+        ));
+        assert_eq!(image.hardware.sector(FIRST_SECTOR), before);
+        assert!(matches!(
+            load(&image.hardware),
+            Err(Error::PartitionOverlap)
+        ));
+
+        let mut image = FatImage::fat16_at(2048);
+        assert!(save(&world(), &mut image.hardware).is_ok());
+        assert!(load(&image.hardware).is_ok());
+    }
+
+    #[test]
+    fn card_not_ready() {
+        let mut hardware = HardwareMemory::new(2048);
+        hardware.sdcard_status = 0;
+        assert!(matches!(
+            save(&world(), &mut hardware),
+            Err(Error::CardNotReady)
+        ));
+        assert!(matches!(load(&hardware), Err(Error::CardNotReady)));
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
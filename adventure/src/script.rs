@@ -0,0 +1,292 @@
+//
+// runs a script of commands line by line to create a world
+//
+// note: lines are run as read thus a script is not limited in size, lines that are empty or
+//       start with '#' are skipped
+//
+use crate::actions::{ActionContext, CommandBuffer};
+use crate::commands::handle_input;
+use crate::lib::api::{Hardware, Printer, SDCARD_SECTOR_SIZE_BYTES};
+use crate::lib::arena::Id;
+use crate::lib::fat;
+use crate::model::{EntityId, World};
+
+/// Script run at boot if there is no saved world.
+pub const FILE_PATH: &[u8] = b"/WORLD.TXT";
+
+/// Entity 0 that runs the commands.
+const ENTITY: EntityId = Id::new(0, 0);
+
+pub struct Error {
+    /// Line number starting at 1.
+    pub line: u32,
+    pub kind: ErrorKind,
+}
+
+pub enum ErrorKind {
+    /// Line does not fit in the command buffer.
+    LineTooLong,
+    /// Command in the line failed.
+    Command,
+    /// Script could not be read.
+    Read(fat::Error),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Runs the script file at `path` on the SD card as commands of entity 0.
+pub fn run_file(
+    printer: &mut dyn Printer,
+    hardware: &mut dyn Hardware,
+    world: &mut World,
+    path: &[u8],
+) -> Result<()> {
+    let read_error = |e| Error {
+        line: 0,
+        kind: ErrorKind::Read(e),
+    };
+
+    let volume = fat::mount(hardware).map_err(read_error)?;
+    let entry = volume
+        .resolve(hardware, fat::ROOT, path)
+        .map_err(read_error)?;
+    let mut cursor = volume.open(&entry).map_err(read_error)?;
+
+    run(printer, hardware, world, |hardware, buffer| {
+        volume.read_next(hardware, &mut cursor, buffer)
+    })
+}
+
+/// Runs `script` as commands of entity 0.
+pub fn run_bytes(
+    printer: &mut dyn Printer,
+    hardware: &mut dyn Hardware,
+    world: &mut World,
+    script: &[u8],
+) -> Result<()> {
+    let mut chunks = script.chunks(SDCARD_SECTOR_SIZE_BYTES);
+    run(printer, hardware, world, |_, buffer| {
+        Ok(chunks.next().map_or(0, |chunk| {
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            chunk.len()
+        }))
+    })
+}
+
+/// Runs the lines of the bytes returned by `read` until it returns 0.
+fn run<R>(
+    printer: &mut dyn Printer,
+    hardware: &mut dyn Hardware,
+    world: &mut World,
+    mut read: R,
+) -> Result<()>
+where
+    R: FnMut(&dyn Hardware, &mut [u8; SDCARD_SECTOR_SIZE_BYTES]) -> fat::Result<usize>,
+{
+    let mut working_directory = fat::ROOT;
+    let mut line = CommandBuffer::new();
+    let mut line_number = 1;
+    let mut buffer = [0_u8; SDCARD_SECTOR_SIZE_BYTES];
+
+    loop {
+        let len = read(hardware, &mut buffer).map_err(|e| Error {
+            line: line_number,
+            kind: ErrorKind::Read(e),
+        })?;
+        if len == 0 {
+            break;
+        }
+
+        for &byte in &buffer[..len] {
+            match byte {
+                b'\n' => {
+                    let mut ctx = ActionContext {
+                        printer,
+                        hardware,
+                        world,
+                        entity: ENTITY,
+                        tokens: &mut line.iter_tokens(u8::is_ascii_whitespace),
+                        working_directory: &mut working_directory,
+                    };
+                    run_line(&mut ctx, line_number)?;
+                    line = CommandBuffer::new();
+                    line_number += 1;
+                }
+                b'\r' => {}
+                _ => {
+                    if line.insert(byte).is_err() {
+                        return Err(Error {
+                            line: line_number,
+                            kind: ErrorKind::LineTooLong,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // note: last line without new line
+    let mut ctx = ActionContext {
+        printer,
+        hardware,
+        world,
+        entity: ENTITY,
+        tokens: &mut line.iter_tokens(u8::is_ascii_whitespace),
+        working_directory: &mut working_directory,
+    };
+    run_line(&mut ctx, line_number)
+}
+
+fn run_line(ctx: &mut ActionContext, line_number: u32) -> Result<()> {
+    if ctx.tokens.rest().is_empty() || ctx.tokens.rest().starts_with(b"#") {
+        return Ok(());
+    }
+
+    handle_input(ctx).map_err(|_| Error {
+        line: line_number,
+        kind: ErrorKind::Command,
+    })?;
+
+    // clear messages on all entities in case input generated messages
+    ctx.world
+        .entities
+        .values_mut()
+        .for_each(|x| x.messages.clear());
+
+    Ok(())
+}
+
+pub fn print_error(printer: &dyn Printer, error: &Error) {
+    let message: &[u8] = match error.kind {
+        ErrorKind::LineTooLong => b"line too long",
+        ErrorKind::Command => b"command failed",
+        ErrorKind::Read(_) => b"cannot read script",
+    };
+    printer.p(b"world script line ");
+    printer.p_u32(error.line);
+    printer.p(b": ");
+    printer.pl(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::api::PrinterVoid;
+    use crate::lib::api_memory::HardwareMemory;
+    use crate::lib::arena::Arena;
+    use crate::lib::fat_image::FatImage;
+    use crate::model::{DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, Entity, Location, Name, Note};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn world() -> World {
+        World {
+            entities: Arena::from(vec![Entity {
+                name: Name::from(b"u"),
+                description: Note::default(),
+                location: Id::new(0, 0),
+                objects: vec![],
+                messages: vec![],
+                max_weight: DEFAULT_MAX_WEIGHT,
+                max_bulk: DEFAULT_MAX_BULK,
+            }]),
+            locations: Arena::from(vec![Location {
+                name: Name::from(b"roome"),
+                note: Note::default(),
+                links: vec![],
+                objects: vec![],
+                entities: vec![Id::new(0, 0)],
+            }]),
+            objects: Arena::new(),
+            link_names: Arena::new(),
+        }
+    }
+
+    fn run_script(script: &[u8]) -> (World, Result<()>) {
+        let mut world = world();
+        let mut hardware = HardwareMemory::new(1);
+        let result = run_bytes(&mut PrinterVoid::new(), &mut hardware, &mut world, script);
+        (world, result)
+    }
+
+    #[test]
+    fn runs_lines_as_commands() {
+        let (world, result) =
+            run_script(b"# kitchen\r\nnl west east kitchen\r\n\r\n  \nno pan\nne me");
+        assert!(result.is_ok());
+        assert_eq!(
+            world.locations.find(|x| &*x.name == b"kitchen"),
+            Some(Id::new(1, 0))
+        );
+        assert_eq!(
+            world.objects.find(|x| &*x.name == b"pan"),
+            Some(Id::new(0, 0))
+        );
+        assert_eq!(
+            world.entities.find(|x| &*x.name == b"me"),
+            Some(Id::new(1, 0))
+        );
+        assert!(world.entities.values().all(|x| x.messages.is_empty()));
+    }
+
+    #[test]
+    fn reports_line_of_failed_command() {
+        let (world, result) = run_script(b"no pan\n\nfly away\nno cup\n");
+        let Err(e) = result else {
+            panic!("expected script to fail");
+        };
+        assert_eq!(e.line, 3);
+        assert!(matches!(e.kind, ErrorKind::Command));
+        assert_eq!(world.objects.len(), 1);
+    }
+
+    #[test]
+    fn reports_line_too_long() {
+        let mut script = b"no pan\nsln ".to_vec();
+        script.resize(script.len() + 1000, b'x');
+        let (_, result) = run_script(&script);
+        let Err(e) = result else {
+            panic!("expected script to fail");
+        };
+        assert_eq!(e.line, 2);
+        assert!(matches!(e.kind, ErrorKind::LineTooLong));
+    }
+
+    #[test]
+    fn runs_file_larger_than_command_buffer() {
+        let mut script = Vec::new();
+        for i in 0..100_u8 {
+            script.extend_from_slice(b"no o");
+            script.push(b'0' + i / 10);
+            script.push(b'0' + i % 10);
+            script.push(b'\n');
+        }
+        let mut image = FatImage::fat16();
+        image.file(fat::ROOT, b"WORLD.TXT", &script);
+
+        let mut world = world();
+        let result = run_file(
+            &mut PrinterVoid::new(),
+            &mut image.hardware,
+            &mut world,
+            FILE_PATH,
+        );
+        assert!(result.is_ok());
+        assert_eq!(world.objects.len(), 100);
+        assert!(world.objects.find(|x| &*x.name == b"o99").is_some());
+
+        let result = run_file(
+            &mut PrinterVoid::new(),
+            &mut image.hardware,
+            &mut world,
+            b"missing.txt",
+        );
+        assert!(matches!(
+            result,
+            Err(Error {
+                line: 0,
+                kind: ErrorKind::Read(fat::Error::NotFound)
+            })
+        ));
+    }
+}
@@ -12,6 +12,19 @@ os.chdir(script_dir)
 
 card = sys.argv[1]
 
+memory_end = 0x0080_0000
+stack_size = 0x0001_0000
+stack_sentinel = 0x5354_414b  # "STAK"
+
+
+def rust_hex(value):
+    return f"0x{value >> 16:04x}_{value & 0xffff:04x}"
+
+
+def cpp_hex(value):
+    return f"0x{value >> 16:04x}'{value & 0xffff:04x}"
+
+
 print(f"configure for Tang Nano {card}")
 print("* `src/startup.s`")
 with open("src/startup.s", "w") as file:
@@ -28,8 +41,18 @@ with open("src/startup.s", "w") as file:
     file.write("    addi a0, a0, 1\n")
     file.write("    j .bss_clear_loop\n")
     file.write(".bss_clear_done:\n")
+    file.write("    # paint stack with sentinel to measure maximum stack usage\n")
+    file.write(f"    li a0, {memory_end - stack_size:#x}\n")
+    file.write(f"    li a1, {memory_end:#x}\n")
+    file.write(f"    li a2, {stack_sentinel:#x}\n")
+    file.write(".stack_paint_loop:\n")
+    file.write("    bgeu a0, a1, .stack_paint_done\n")
+    file.write("    sw a2, 0(a0)\n")
+    file.write("    addi a0, a0, 4\n")
+    file.write("    j .stack_paint_loop\n")
+    file.write(".stack_paint_done:\n")
     file.write("    # set stack pointer and enter program\n")
-    file.write("    li sp, 0x800000\n")
+    file.write(f"    li sp, {memory_end:#x}\n")
     file.write("    j run\n")
 
 print("* `src/lib/constants.rs`")
@@ -43,7 +66,9 @@ with open("src/lib/constants.rs", "w") as file:
     file.write("pub const SDCARD_NEXT_BYTE: u32 = 0xffff_ffe8;\n")
     file.write("pub const SDCARD_STATUS: u32 = 0xffff_ffe4;\n")
     file.write("pub const SDCARD_WRITE_SECTOR: u32 = 0xffff_ffe0;\n")
-    file.write("pub const MEMORY_END: u32 = 0x0080_0000;\n")
+    file.write(f"pub const MEMORY_END: u32 = {rust_hex(memory_end)};\n")
+    file.write(f"pub const STACK_SIZE: u32 = {rust_hex(stack_size)};\n")
+    file.write(f"pub const STACK_SENTINEL: u32 = {rust_hex(stack_sentinel)};\n")
 
 print("* `emulator/src/main_config.hpp`")
 with open("emulator/src/main_config.hpp", "w") as file:
@@ -63,7 +88,7 @@ with open("emulator/src/main_config.hpp", "w") as file:
     file.write("std::uint32_t constexpr sdcard_status = 0xffff'ffe4;\n")
     file.write("std::uint32_t constexpr sdcard_write_sector = 0xffff'ffe0;\n")
     file.write("std::uint32_t constexpr io_addresses_start = 0xffff'ffe0;\n")
-    file.write("std::uint32_t constexpr memory_end = 0x0080'0000;\n")
+    file.write(f"std::uint32_t constexpr memory_end = {cpp_hex(memory_end)};\n")
     file.write("\n")
     file.write("} // namespace osqa\n")
 
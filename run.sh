@@ -25,6 +25,8 @@ emulator/make.sh
 emulator/qa/test.sh
 
 # run tests
+echo " * run library tests on host"
+(cd adventure && cargo test --quiet)
 qa/emulator/test.sh
 
 echo " * run emulator"
@@ -2,6 +2,8 @@
 set -e
 cd $(dirname "$0")
 
+# note: arguments are passed to cargo such as `--features slab`
+
 ELF=target/riscv32i-unknown-none-elf/release/firmware
 OBJCOPY=riscv64-elf-objcopy
 OBJDUMP=riscv64-elf-objdump
@@ -15,8 +17,8 @@ FIRMWARE_TMP="$FIRMWARE.img.tmp"
 cd ..
 
 cargo clean
-cargo clippy --release
-cargo build --release
+cargo clippy --release "$@"
+cargo build --release "$@"
 
 # Check if firmware.img already exists and make a backup.
 if [ -f "$FIRMWARE_IMG" ]; then
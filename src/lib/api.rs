@@ -3,10 +3,13 @@
 //           2026-08-21
 //
 use super::api_unsafe::{
-    __heap_start__, SDCARD_SECTOR_SIZE_BYTES, led_set, memory_stack_pointer, sdcard_read_blocking,
+    __heap_start__, led_set, memory_read_u32, memory_stack_pointer, sdcard_read_blocking,
     sdcard_status, sdcard_write_blocking, uart_read_byte, uart_send_byte,
 };
-use super::constants::MEMORY_END;
+use super::constants::{MEMORY_END, STACK_SENTINEL, STACK_SIZE};
+use super::global_allocator::GlobalAllocator;
+use adventure::lib::api::{Hardware, HeapStats, Printer};
+use core::hint::spin_loop;
 
 pub struct Memory;
 
@@ -22,6 +25,41 @@ impl Memory {
     pub fn stack_pointer() -> u32 {
         memory_stack_pointer()
     }
+
+    /// Lowest address of the stack reservation and end of the heap.
+    pub const fn stack_bottom() -> u32 {
+        MEMORY_END - STACK_SIZE
+    }
+
+    /// Returns the maximum number of bytes of stack used since boot.
+    /// note: `startup.s` paints the stack with `STACK_SENTINEL`
+    pub fn stack_peak() -> u32 {
+        let mut address = Self::stack_bottom();
+        while address < MEMORY_END && memory_read_u32(address) == STACK_SENTINEL {
+            address += 4;
+        }
+        MEMORY_END - address
+    }
+
+    /// Halts with a fault message if the stack has grown into the heap.
+    pub fn check_stack() {
+        if Self::stack_pointer() < Self::stack_bottom()
+            || memory_read_u32(Self::stack_bottom()) != STACK_SENTINEL
+        {
+            Self::fault(b"stack overflow");
+        }
+    }
+
+    /// Halts with a fault message.
+    pub fn fault(message: &[u8]) -> ! {
+        Leds::set(0b0000); // turn on all leds
+        let printer = PrinterUart::new();
+        printer.p(b"FAULT: ");
+        printer.pl(message);
+        loop {
+            spin_loop();
+        }
+    }
 }
 
 pub struct Uart;
@@ -58,85 +96,6 @@ impl SDCard {
     pub fn write_blocking(sector: u32, buffer_512_bytes: &[u8]) {
         sdcard_write_blocking(sector, buffer_512_bytes);
     }
-
-    pub const fn sector_size_bytes() -> usize {
-        SDCARD_SECTOR_SIZE_BYTES
-    }
-}
-
-pub trait Printer {
-    /// Prints a byte.
-    fn pb(&self, byte: u8);
-
-    /// Prints implementation specific new line.
-    fn nl(&self);
-
-    /// Prints a slice of bytes.
-    fn p(&self, bytes: &[u8]) {
-        for &byte in bytes {
-            self.pb(byte);
-        }
-    }
-
-    /// Prints implementation specific multiple new lines.
-    fn nlc(&self, count: usize) {
-        for _ in 0..count {
-            self.nl();
-        }
-    }
-
-    /// Prints a slice of bytes followed by implementation specific new line.
-    fn pl(&self, bytes: &[u8]) {
-        self.p(bytes);
-        self.nl();
-    }
-
-    /// Prints a 4-bit unsigned integer as hexadecimal.
-    fn p_hex_nibble(&self, nibble: u8) {
-        if nibble < 10 {
-            self.pb(b'0' + nibble);
-        } else {
-            self.pb(b'A' + (nibble - 10));
-        }
-    }
-
-    /// Prints a 8-bit unsigned integer as hexadecimal.
-    fn p_hex_u8(&self, i: u8) {
-        self.p_hex_nibble(i >> 4);
-        self.p_hex_nibble(i & 0x0f);
-    }
-
-    /// Prints a 32-bit unsigned integer as hexadecimal.
-    #[allow(clippy::cast_possible_truncation, reason = "intended behavior")]
-    fn p_hex_u32(&self, i: u32, separate_half_words: bool) {
-        self.p_hex_u8((i >> 24) as u8);
-        self.p_hex_u8((i >> 16) as u8);
-        if separate_half_words {
-            self.pb(b':');
-        }
-        self.p_hex_u8((i >> 8) as u8);
-        self.p_hex_u8(i as u8);
-    }
-
-    /// Prints a 32-bit unsigned integer.
-    fn p_u32(&self, num: u32) {
-        if num == 0 {
-            self.pb(b'0');
-            return;
-        }
-
-        let mut n = num;
-        let mut buf = [0_u8; 10];
-        // note: 10 is maximum digits for u32
-        let mut pos = buf.len();
-        while n > 0 {
-            pos -= 1;
-            buf[pos] = b'0' + (n % 10) as u8;
-            n /= 10;
-        }
-
-        self.p(&buf[pos..]);
-    }
 }
 
 /// Printer that writes to UART.
@@ -158,52 +117,61 @@ impl Printer for PrinterUart {
     }
 }
 
-/// Printer that writes nothing.
-pub struct PrinterVoid;
+/// Hardware that is accessed through memory mapped IO.
+pub struct HardwareMmio;
 
-impl PrinterVoid {
+impl HardwareMmio {
     pub const fn new() -> Self {
         Self
     }
 }
 
-impl Printer for PrinterVoid {
-    fn pb(&self, _: u8) {}
-    fn nl(&self) {}
-    fn nlc(&self, _: usize) {}
-    fn p(&self, _: &[u8]) {}
-    fn pl(&self, _: &[u8]) {}
-    fn p_hex_nibble(&self, _: u8) {}
-    fn p_hex_u8(&self, _: u8) {}
-    fn p_hex_u32(&self, _: u32, _: bool) {}
-    fn p_u32(&self, _: u32) {}
-}
+impl Hardware for HardwareMmio {
+    fn led_set(&mut self, bits_low_being_on: u32) {
+        Leds::set(bits_low_being_on);
+    }
 
-pub fn u8_slice_to_u32(number_as_str: &[u8]) -> u32 {
-    let mut num = 0;
-    for &ch in number_as_str {
-        if !ch.is_ascii_digit() {
-            break;
-        }
-        num = num * 10 + u32::from(ch - b'0');
+    fn sdcard_status(&self) -> i32 {
+        SDCard::status()
     }
-    num
-}
 
-pub fn u8_slice_bits_to_u32(binary_as_str: &[u8]) -> u32 {
-    if binary_as_str.is_empty() {
-        return 0;
+    fn sdcard_read_blocking(&self, sector: u32, buffer_512_bytes: &mut [u8]) {
+        SDCard::read_blocking(sector, buffer_512_bytes);
     }
-    let mut num = 0;
-    let mut bit_value = 1 << (binary_as_str.len() - 1);
-    for &ch in binary_as_str {
-        if ch != b'0' && ch != b'1' {
-            break;
-        }
-        if ch == b'1' {
-            num += bit_value;
-        }
-        bit_value >>= 1;
+
+    fn sdcard_write_blocking(&mut self, sector: u32, buffer_512_bytes: &[u8]) {
+        SDCard::write_blocking(sector, buffer_512_bytes);
+    }
+
+    fn memory_heap_start(&self) -> u32 {
+        Memory::heap_start()
+    }
+
+    fn memory_stack_pointer(&self) -> u32 {
+        Memory::stack_pointer()
+    }
+
+    fn memory_end(&self) -> u32 {
+        Memory::end()
+    }
+
+    fn memory_stack_bottom(&self) -> u32 {
+        Memory::stack_bottom()
+    }
+
+    fn memory_stack_peak(&self) -> u32 {
+        Memory::stack_peak()
+    }
+
+    fn memory_debug_block_list(&self, printer: &dyn Printer) {
+        GlobalAllocator::debug_block_list(printer);
+    }
+
+    fn memory_heap_check(&self, printer: &dyn Printer) -> u32 {
+        GlobalAllocator::check_block_list(printer)
+    }
+
+    fn memory_heap_stats(&self) -> HeapStats {
+        GlobalAllocator::heap_stats()
     }
-    num
 }
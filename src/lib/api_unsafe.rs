@@ -6,11 +6,10 @@ use super::constants::{
     LED, SDCARD_BUSY, SDCARD_NEXT_BYTE, SDCARD_READ_SECTOR, SDCARD_STATUS, SDCARD_WRITE_SECTOR,
     UART_IN_ADDR, UART_OUT_ADDR,
 };
+use adventure::lib::api::SDCARD_SECTOR_SIZE_BYTES;
 use core::arch::asm;
 use core::hint::spin_loop;
 
-pub const SDCARD_SECTOR_SIZE_BYTES: usize = 512;
-
 unsafe extern "C" {
     pub static __heap_start__: u8;
     // note: declared in `linker.ld`
@@ -54,6 +53,10 @@ pub fn memory_stack_pointer() -> u32 {
     sp
 }
 
+pub fn memory_read_u32(address: u32) -> u32 {
+    unsafe { (address as *const u32).read_volatile() }
+}
+
 pub fn sdcard_status() -> i32 {
     unsafe { (SDCARD_STATUS as *const i32).read_volatile() }
 }
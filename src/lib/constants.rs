@@ -8,3 +8,5 @@ pub const SDCARD_NEXT_BYTE: u32 = 0xffff_ffe8;
 pub const SDCARD_STATUS: u32 = 0xffff_ffe4;
 pub const SDCARD_WRITE_SECTOR: u32 = 0xffff_ffe0;
 pub const MEMORY_END: u32 = 0x0080_0000;
+pub const STACK_SIZE: u32 = 0x0001_0000;
+pub const STACK_SENTINEL: u32 = 0x5354_414b;
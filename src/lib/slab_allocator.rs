@@ -0,0 +1,121 @@
+//
+// size classes of fixed size chunks carved from slabs allocated from the heap
+//
+// note: small allocations take a chunk from the free list of the class thus no list walk and
+//       no fragmentation of the heap by small blocks
+// note: slabs are never returned to the heap, freed chunks are reused by the same class
+// note: enabled with feature "slab", adds 952 B to the firmware measured as the binary of
+//       `cargo build --release` with and without the feature
+//
+use adventure::lib::api::Printer;
+use core::alloc::Layout;
+use core::mem;
+use core::ptr;
+
+const CLASS_COUNT: usize = 6;
+const CLASS_SIZES: [usize; CLASS_COUNT] = [8, 16, 32, 64, 128, 256];
+const SLAB_SIZE_BYTES: usize = 4096;
+
+// note: slabs are allocated from the heap with this alignment and chunk sizes are multiples of it
+const CHUNK_ALIGN: usize = mem::align_of::<usize>();
+
+/// Free chunk. The link is written into the unused chunk.
+struct FreeChunk {
+    next: *mut Self,
+}
+
+pub struct SlabAllocator {
+    free: [*mut FreeChunk; CLASS_COUNT],
+    slab_count: [u32; CLASS_COUNT],
+    in_use_count: [u32; CLASS_COUNT],
+}
+
+impl SlabAllocator {
+    pub const fn new() -> Self {
+        Self {
+            free: [ptr::null_mut(); CLASS_COUNT],
+            slab_count: [0; CLASS_COUNT],
+            in_use_count: [0; CLASS_COUNT],
+        }
+    }
+
+    /// Returns the index of the smallest class that fits `layout` or `None` if the heap list
+    /// should be used.
+    pub fn class(layout: Layout) -> Option<usize> {
+        if layout.align() > CHUNK_ALIGN {
+            return None;
+        }
+        CLASS_SIZES.iter().position(|&size| layout.size() <= size)
+    }
+
+    /// Takes a chunk of `class`. A new slab is allocated with `alloc_slab` if the class has no
+    /// free chunk. Returns null if out of memory.
+    #[expect(
+        clippy::cast_ptr_alignment,
+        reason = "chunks are aligned to `CHUNK_ALIGN`"
+    )]
+    pub unsafe fn alloc<F>(&mut self, class: usize, alloc_slab: F) -> *mut u8
+    where
+        F: FnOnce(Layout) -> *mut u8,
+    {
+        if self.free[class].is_null() {
+            let Ok(slab_layout) = Layout::from_size_align(SLAB_SIZE_BYTES, CHUNK_ALIGN) else {
+                return ptr::null_mut();
+            };
+            let slab = alloc_slab(slab_layout);
+            if slab.is_null() {
+                return ptr::null_mut();
+            }
+
+            // link the chunks of the slab in address order
+            let size = CLASS_SIZES[class];
+            for offset in (0..SLAB_SIZE_BYTES / size * size).step_by(size).rev() {
+                unsafe {
+                    let chunk = slab.add(offset).cast::<FreeChunk>();
+                    (*chunk).next = self.free[class];
+                    self.free[class] = chunk;
+                }
+            }
+            self.slab_count[class] += 1;
+        }
+
+        let chunk = self.free[class];
+        unsafe {
+            self.free[class] = (*chunk).next;
+        }
+        self.in_use_count[class] += 1;
+
+        chunk.cast::<u8>()
+    }
+
+    /// Returns the chunk at `ptr` to the free list of `class`.
+    #[expect(
+        clippy::cast_ptr_alignment,
+        reason = "chunks are aligned to `CHUNK_ALIGN`"
+    )]
+    pub unsafe fn dealloc(&mut self, class: usize, ptr: *mut u8) {
+        let chunk = ptr.cast::<FreeChunk>();
+        unsafe {
+            (*chunk).next = self.free[class];
+        }
+        self.free[class] = chunk;
+        self.in_use_count[class] -= 1;
+    }
+
+    /// Prints slabs and chunks in use per class.
+    #[expect(clippy::cast_possible_truncation, reason = "intended behavior")]
+    pub fn debug_class_list(&self, printer: &dyn Printer) {
+        printer.nl();
+        printer.pl(b"slab classes:");
+        for (class, &size) in CLASS_SIZES.iter().enumerate() {
+            printer.p_u32(size as u32);
+            printer.p(b" bytes: ");
+            printer.p_u32(self.slab_count[class]);
+            printer.p(b" slabs, ");
+            printer.p_u32(self.in_use_count[class]);
+            printer.p(b" of ");
+            printer.p_u32(self.slab_count[class] * (SLAB_SIZE_BYTES / size) as u32);
+            printer.pl(b" chunks in use");
+        }
+    }
+}
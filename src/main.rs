@@ -36,31 +36,6 @@ static ASCII_ART: &[u8] = b"\x20                                  oOo.o.\r\n\
 \x20      | |\r\n\
 \r\n";
 
-static HELP: &[u8] = b"command:\r
-\x20 go <exit>: go\r
-\x20 n: go north\r
-\x20 e: go east\r
-\x20 s: go south\r
-\x20 w: go west\r
-\x20 i: display inventory\r
-\x20 t <object>: take object\r
-\x20 d <object>: drop object\r
-\x20 g <entity> <object>: give entity object from inventory\r
-\x20 say <what>: say to all in location\r
-\x20 tell <whom> <what>: tells entity in location\r
-\x20 sln <text>: set location note\r
-\x20 sds: SD card status (6 is ok)\r
-\x20 sdr <sector>: read sector from SD card\r
-\x20 sdw <sector> <text>: write sector to SD card\r
-\x20 led <bits with 1 being on>: turn on/off leds\r
-\x20 no <object name>: new object into current inventory\r
-\x20 nl <to link> <back link> <new location name>: new linked location\r
-\x20 ne <name>: create new entity\r
-\x20 mi: memory allocation info\r
-\x20 wait: do nothing\r
-\x20 help: this message\r
-\r";
-
 static CREATION: &[u8] = b"sln todo: find an exit
 nl none back office
 go none
@@ -80,24 +55,32 @@ mod lib {
     pub mod api;
     mod api_unsafe;
     pub mod constants;
-    pub mod cursor_buffer;
-    pub mod fixed_size_string;
     pub mod global_allocator;
+    #[cfg(feature = "slab")]
+    pub mod slab_allocator;
 }
-mod actions;
-mod model;
 
-use actions::{ActionContext, CommandBuffer, Error, Result};
+use adventure::actions::{self, ActionContext, CommandBuffer, CommandHistory};
+use adventure::commands::handle_input;
+use adventure::completion;
+use adventure::lib::api::{Printer, PrinterVoid};
+use adventure::lib::arena::{Arena, Id};
+use adventure::lib::fat;
+use adventure::model::{
+    DEFAULT_MAX_BULK, DEFAULT_MAX_WEIGHT, Entity, EntityId, Location, Name, Note, World,
+};
+use adventure::persistence;
+use adventure::script;
 use alloc::vec;
 use core::arch::global_asm;
 use core::hint::spin_loop;
 use core::panic::PanicInfo;
-use lib::api::{Leds, Printer, PrinterUart, PrinterVoid, Uart};
+use lib::api::{HardwareMmio, Leds, Memory, PrinterUart, Uart};
 use lib::global_allocator::GlobalAllocator;
-use model::{Entity, Location, Name, Note, World};
 
 const CHAR_CARRIAGE_RETURN: u8 = 0xd;
 const CHAR_BACKSPACE: u8 = 0x7f;
+const CHAR_TAB: u8 = 9;
 const CHAR_ESCAPE: u8 = 0x1b;
 const CHAR_CTRL_A: u8 = 1;
 const CHAR_CTRL_E: u8 = 5;
@@ -116,23 +99,41 @@ pub extern "C" fn run() -> ! {
 
     GlobalAllocator::init();
 
-    let mut world = create_world();
-
     let mut printer = PrinterUart::new();
+    let mut hardware = HardwareMmio::new();
 
     printer.p(ASCII_ART);
     printer.p(HELLO);
 
+    let mut world = persistence::load(&hardware).map_or_else(
+        |_| create_world(&printer, &mut hardware),
+        |world| {
+            printer.pl(b"world restored from SD card");
+            printer.nl();
+            world
+        },
+    );
+
+    let mut history = CommandHistory::new();
+    let mut working_directory = fat::ROOT;
+
     loop {
-        for entity_id in 0..world.entities.len() {
+        for index in 0..world.entities.slot_count() {
+            let Some(entity_id) = world.entities.id_at(index) else {
+                // note: slot is vacant or `load` replaced the world with one having fewer entities
+                continue;
+            };
+
             {
                 // note: for consistency `action_look` requires `ActionContext`
                 let command_buffer = CommandBuffer::new();
                 let mut ctx = ActionContext {
                     printer: &mut printer,
+                    hardware: &mut hardware,
                     world: &mut world,
                     entity: entity_id,
                     tokens: &mut command_buffer.iter_tokens(u8::is_ascii_whitespace),
+                    working_directory: &mut working_directory,
                 };
 
                 assert!(actions::look(&mut ctx).is_ok(), "cannot look");
@@ -141,21 +142,31 @@ pub extern "C" fn run() -> ! {
             loop {
                 // loop until action succeeds
 
-                printer.p(&world.entities[entity_id].name);
-                printer.p(b" > ");
+                print_prompt(&printer, &world, entity_id);
 
                 let mut command_buffer = CommandBuffer::new();
-                input(&mut command_buffer, &printer);
+                input(
+                    &mut command_buffer,
+                    &mut history,
+                    &printer,
+                    &world,
+                    entity_id,
+                );
                 printer.nl();
+                history.push(&command_buffer);
 
                 let mut ctx = ActionContext {
                     printer: &mut printer,
+                    hardware: &mut hardware,
                     world: &mut world,
                     entity: entity_id,
                     tokens: &mut command_buffer.iter_tokens(u8::is_ascii_whitespace),
+                    working_directory: &mut working_directory,
                 };
 
-                if handle_input(&mut ctx).is_ok() {
+                let result = handle_input(&mut ctx);
+                Memory::check_stack();
+                if result.is_ok() {
                     break;
                 }
             }
@@ -163,50 +174,28 @@ pub extern "C" fn run() -> ! {
     }
 }
 
-fn handle_input(ctx: &mut ActionContext) -> Result<()> {
-    match ctx.tokens.next() {
-        Some(b"go") => actions::go(ctx)?,
-        Some(b"n") => actions::go_named_link(ctx, b"north")?,
-        Some(b"e") => actions::go_named_link(ctx, b"east")?,
-        Some(b"s") => actions::go_named_link(ctx, b"south")?,
-        Some(b"w") => actions::go_named_link(ctx, b"west")?,
-        Some(b"i") => actions::inventory(ctx)?,
-        Some(b"t") => actions::take(ctx)?,
-        Some(b"d") => actions::drop(ctx)?,
-        Some(b"g") => actions::give(ctx)?,
-        Some(b"say") => actions::say(ctx)?,
-        Some(b"tell") => actions::tell(ctx)?,
-        Some(b"sln") => actions::set_location_note(ctx)?,
-        Some(b"sds") => actions::sdcard_status(ctx)?,
-        Some(b"sdr") => actions::sdcard_read(ctx)?,
-        Some(b"sdw") => actions::sdcard_write(ctx)?,
-        Some(b"led") => actions::led_set(ctx)?,
-        Some(b"no") => actions::new_object(ctx)?,
-        Some(b"nl") => actions::new_location(ctx)?,
-        Some(b"ne") => actions::new_entity(ctx)?,
-        Some(b"mi") => actions::memory_info(ctx)?,
-        Some(b"wait") => actions::wait(ctx)?,
-        Some(b"help") => actions::help(ctx, HELP)?,
-        _ => {
-            ctx.printer.p(b"not understood");
-            ctx.printer.nlc(2);
-            return Err(Error::NotUnderstood);
-        }
+fn print_prompt(printer: &PrinterUart, world: &World, entity_id: EntityId) {
+    if let Some(entity) = world.entities.get(entity_id) {
+        printer.p(&entity.name);
     }
-
-    ctx.printer.nl();
-
-    Ok(())
+    printer.p(b" > ");
 }
 
-fn input(command_buffer: &mut CommandBuffer, printer: &PrinterUart) {
+fn input(
+    command_buffer: &mut CommandBuffer,
+    history: &mut CommandHistory,
+    printer: &PrinterUart,
+    world: &World,
+    entity_id: EntityId,
+) {
     loop {
         let ch = Uart::read_blocking();
         Leds::set(!u32::from(ch));
 
         match ch {
-            CHAR_ESCAPE => input_escape_sequence(command_buffer, printer),
+            CHAR_ESCAPE => input_escape_sequence(command_buffer, history, printer),
             CHAR_BACKSPACE => input_backspace(command_buffer, printer),
+            CHAR_TAB => input_complete(command_buffer, printer, world, entity_id),
             CHAR_CARRIAGE_RETURN => return,
             CHAR_FORM_FEED => {} // ignore CTRL+L
             CHAR_CTRL_A => input_move_to_start_of_line(command_buffer, printer),
@@ -217,7 +206,11 @@ fn input(command_buffer: &mut CommandBuffer, printer: &PrinterUart) {
     }
 }
 
-fn input_escape_sequence(command_buffer: &mut CommandBuffer, printer: &PrinterUart) {
+fn input_escape_sequence(
+    command_buffer: &mut CommandBuffer,
+    history: &mut CommandHistory,
+    printer: &PrinterUart,
+) {
     if Uart::read_blocking() != b'[' {
         return;
     }
@@ -229,6 +222,8 @@ fn input_escape_sequence(command_buffer: &mut CommandBuffer, printer: &PrinterUa
             parameter = parameter * 10 + (ch - b'0');
         } else {
             match ch {
+                b'A' => input_recall(command_buffer, printer, |x| history.previous(x)),
+                b'B' => input_recall(command_buffer, printer, |x| history.next(x)),
                 b'D' => {
                     let steps = command_buffer.move_cursor_left();
                     if steps != 0 {
@@ -258,6 +253,70 @@ fn input_escape_sequence(command_buffer: &mut CommandBuffer, printer: &PrinterUa
     }
 }
 
+fn input_recall<F>(command_buffer: &mut CommandBuffer, printer: &PrinterUart, recall: F)
+where
+    F: FnOnce(&mut CommandBuffer) -> bool,
+{
+    let mut line = *command_buffer;
+    if !recall(&mut line) {
+        return;
+    }
+
+    // move to start of line, print recalled line and erase the rest of the previous one
+    input_move_to_start_of_line(command_buffer, printer);
+    *command_buffer = line;
+    command_buffer.move_cursor_to_start_of_line();
+    command_buffer.for_each_from_cursor(|&x| printer.pb(x));
+    command_buffer.move_cursor_to_end_of_line();
+    printer.p(b"\x1B[K");
+}
+
+fn input_complete(
+    command_buffer: &mut CommandBuffer,
+    printer: &PrinterUart,
+    world: &World,
+    entity_id: EntityId,
+) {
+    let line = command_buffer.as_slice();
+    let cursor = line.len() - command_buffer.elements_after_cursor_count();
+    let word_len = cursor - completion::word_start(line, cursor);
+    let (count, common) = completion::complete(world, entity_id, line, cursor);
+
+    // insert the part of the common prefix that is not typed yet
+    for &ch in &common[word_len.min(common.len())..] {
+        input_normal_char(command_buffer, printer, ch);
+    }
+
+    match count {
+        0 => {}
+        1 => {
+            if command_buffer.elements_after_cursor_count() == 0 {
+                input_normal_char(command_buffer, printer, b' ');
+            }
+        }
+        _ => {
+            // list candidates then reprint prompt and line with cursor at same position
+            let line = command_buffer.as_slice();
+            let cursor = line.len() - command_buffer.elements_after_cursor_count();
+            printer.nl();
+            let mut first = true;
+            completion::for_each_candidate(world, entity_id, line, cursor, |name| {
+                if !first {
+                    printer.p(b", ");
+                }
+                first = false;
+                printer.p(name);
+            });
+            printer.nl();
+            print_prompt(printer, world, entity_id);
+            printer.p(line);
+            for _ in 0..command_buffer.elements_after_cursor_count() {
+                printer.pb(CHAR_MOVE_CURSOR_BACK);
+            }
+        }
+    }
+}
+
 fn input_backspace(command_buffer: &mut CommandBuffer, printer: &PrinterUart) {
     if command_buffer.backspace().is_ok() {
         printer.pb(CHAR_BACKSPACE);
@@ -304,45 +363,63 @@ fn input_move_to_end_of_line(command_buffer: &mut CommandBuffer, printer: &Print
     }
 }
 
-fn create_world() -> World {
-    let mut world = World {
-        entities: vec![Entity {
+fn create_world(printer: &PrinterUart, hardware: &mut HardwareMmio) -> World {
+    let mut world = new_world();
+
+    match script::run_file(
+        &mut PrinterVoid::new(),
+        hardware,
+        &mut world,
+        script::FILE_PATH,
+    ) {
+        Ok(()) => {
+            printer.pl(b"world created from WORLD.TXT");
+            printer.nl();
+            return world;
+        }
+        Err(script::Error {
+            kind:
+                script::ErrorKind::Read(
+                    fat::Error::CardNotReady | fat::Error::NoFileSystem | fat::Error::NotFound,
+                ),
+            ..
+        }) => {}
+        Err(e) => {
+            script::print_error(printer, &e);
+            printer.nl();
+            world = new_world();
+        }
+    }
+
+    if let Err(e) = script::run_bytes(&mut PrinterVoid::new(), hardware, &mut world, CREATION) {
+        script::print_error(printer, &e);
+        printer.nl();
+    }
+
+    world
+}
+
+fn new_world() -> World {
+    World {
+        entities: Arena::from(vec![Entity {
             name: Name::from(b"u"),
-            location: 0,
+            description: Note::default(),
+            location: Id::new(0, 0),
             objects: vec![],
             messages: vec![],
-        }],
-        locations: vec![Location {
+            max_weight: DEFAULT_MAX_WEIGHT,
+            max_bulk: DEFAULT_MAX_BULK,
+        }]),
+        locations: Arena::from(vec![Location {
             name: Name::from(b"roome"),
             note: Note::default(),
             links: vec![],
             objects: vec![],
-            entities: vec![0],
-        }],
-        objects: vec![],
-        link_names: vec![],
-    };
-
-    for line in CREATION.split(|&x| x == b'\n') {
-        let mut command_buffer = CommandBuffer::new();
-        for &byte in line {
-            assert!(command_buffer.insert(byte).is_ok(), "command too large");
-        }
-
-        let mut ctx = ActionContext {
-            printer: &mut PrinterVoid::new(),
-            world: &mut world,
-            entity: 0,
-            tokens: &mut command_buffer.iter_tokens(u8::is_ascii_whitespace),
-        };
-
-        assert!(handle_input(&mut ctx).is_ok(), "error creating world");
-
-        // clear messages on all entities in case input generated messages
-        world.entities.iter_mut().for_each(|x| x.messages.clear());
+            entities: vec![Id::new(0, 0)],
+        }]),
+        objects: Arena::new(),
+        link_names: Arena::new(),
     }
-
-    world
 }
 
 #[panic_handler]
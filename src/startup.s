@@ -11,6 +11,16 @@ _start:
     addi a0, a0, 1
     j .bss_clear_loop
 .bss_clear_done:
+    # paint stack with sentinel to measure maximum stack usage
+    li a0, 0x7f0000
+    li a1, 0x800000
+    li a2, 0x5354414b
+.stack_paint_loop:
+    bgeu a0, a1, .stack_paint_done
+    sw a2, 0(a0)
+    addi a0, a0, 4
+    j .stack_paint_loop
+.stack_paint_done:
     # set stack pointer and enter program
     li sp, 0x800000
     j run